@@ -0,0 +1,82 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+// Times the tally of an election with 500 candidates and 100k ballots. Run with
+// `cargo bench --features ssr --bench ranked_pairs`.
+//
+// The ballots are spread over 2000 distinct rankings, as stored in the ballot patterns.
+use std::time::{Duration, Instant};
+
+use friendvote::ballot::{Ballot, WeightedBallot};
+use friendvote::ranked_pairs::calculate_ranks;
+use friendvote::store::PairwiseCounts;
+
+const N_CANDIDATES: usize = 500;
+const N_PATTERNS: usize = 2000;
+const N_BALLOTS: u64 = 100_000;
+const N_RUNS: u32 = 5;
+
+/// xorshift64, so the benchmark is reproducible without extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+}
+
+/// Ballots that rank the candidates by a shared opinion of their quality plus personal noise.
+/// With `noise` large compared to the quality differences, the pairwise results are close and full of cycles.
+fn generate_ballots(rng: &mut Rng, noise: u64) -> Vec<WeightedBallot> {
+	(0..N_PATTERNS)
+		.map(|_| {
+			let mut scores = (0..N_CANDIDATES)
+				.map(|candidate| (candidate as u64 * 1000 + rng.next() % (noise + 1), candidate))
+				.collect::<Vec<_>>();
+			scores.sort_unstable();
+			WeightedBallot {
+				ballot: Ballot::new(scores.into_iter().map(|(_, candidate)| vec![candidate]).collect()),
+				weight: N_BALLOTS / N_PATTERNS as u64,
+			}
+		})
+		.collect()
+}
+
+fn time<T>(mut f: impl FnMut() -> T) -> (Duration, T) {
+	let mut result = f();
+	let mut total = Duration::ZERO;
+	for _ in 0..N_RUNS {
+		let start = Instant::now();
+		result = std::hint::black_box(f());
+		total += start.elapsed();
+	}
+	(total / N_RUNS, result)
+}
+
+fn main() {
+	let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+	for (scenario, noise) in [("clear preferences", 50_000), ("contested", 50_000_000)] {
+		let ballots = generate_ballots(&mut rng, noise);
+		let (tally_time, counts) = time(|| PairwiseCounts::from_ballots(N_CANDIDATES, &ballots));
+		let (ranks_time, ranks) = time(|| calculate_ranks(&counts.defeats_matrix));
+		println!(
+			"{}: {} candidates, {} ballots: tally {:?}, ranked pairs {:?}, {} distinct ranks",
+			scenario,
+			N_CANDIDATES,
+			counts.n_votes,
+			tally_time,
+			ranks_time,
+			ranks.iter().max().map_or(0, |max_rank| max_rank + 1),
+		);
+	}
+}
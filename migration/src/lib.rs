@@ -1,12 +1,39 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_tables;
+mod m20220101_000001_create_tables_portable;
+mod m20250201_000001_add_voting_open;
+mod m20250301_000001_portable_storage;
+mod m20250315_000001_create_audit_entry;
+mod m20250401_000001_create_pairwise_count;
+mod m20250415_000001_create_ballot_pattern;
+mod m20250501_000001_create_results_cache;
+mod m20250515_000001_add_last_vote_at;
+mod m20250601_000001_add_join_code;
+mod m20250615_000001_add_shuffle_candidates;
+mod m20250701_000001_add_review_ballots;
+mod m20250715_000001_create_accounts;
+mod m20250801_000001_add_oidc;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
 	fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-		vec![Box::new(m20220101_000001_create_tables::Migration)]
+		vec![
+			Box::new(m20220101_000001_create_tables_portable::Migration),
+			Box::new(m20250201_000001_add_voting_open::Migration),
+			Box::new(m20250301_000001_portable_storage::Migration),
+			Box::new(m20250315_000001_create_audit_entry::Migration),
+			Box::new(m20250401_000001_create_pairwise_count::Migration),
+			Box::new(m20250415_000001_create_ballot_pattern::Migration),
+			Box::new(m20250501_000001_create_results_cache::Migration),
+			Box::new(m20250515_000001_add_last_vote_at::Migration),
+			Box::new(m20250601_000001_add_join_code::Migration),
+			Box::new(m20250615_000001_add_shuffle_candidates::Migration),
+			Box::new(m20250701_000001_add_review_ballots::Migration),
+			Box::new(m20250715_000001_create_accounts::Migration),
+			Box::new(m20250801_000001_add_oidc::Migration),
+		]
 	}
 }
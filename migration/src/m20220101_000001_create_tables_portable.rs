@@ -0,0 +1,83 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::Table,
+	schema,
+	sea_orm::{self, DbBackend, DeriveIden},
+	sea_query, DbErr, MigrationName, MigrationTrait, SchemaManager,
+};
+
+use crate::m20220101_000001_create_tables;
+
+/// Runs the first migration on every supported database.
+///
+/// On Postgres, this is the original migration, with its array columns.
+/// Other databases have no array columns, so they get the same tables without them,
+/// which is what the portable storage migration turns the Postgres tables into.
+/// Both are recorded under the name of the original, so existing databases see no new migration.
+pub struct Migration;
+
+impl MigrationName for Migration {
+	fn name(&self) -> &str {
+		"m20220101_000001_create_tables"
+	}
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		if manager.get_database_backend() == DbBackend::Postgres {
+			return m20220101_000001_create_tables::Migration.up(manager).await;
+		}
+
+		manager
+			.create_table(
+				Table::create()
+					.table(Election::Table)
+					.col(schema::string_len_uniq(Election::Id, 16).primary_key())
+					.col(schema::text(Election::Name))
+					.col(schema::string_len(Election::AdminId, 16))
+					.col(schema::string_len(Election::ResultId, 16))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_table(
+				Table::create()
+					.table(Votes::Table)
+					.col(schema::pk_auto(Votes::VoteId))
+					.col(schema::string_len(Votes::ElectionId, 16))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_index(
+				sea_query::Index::create()
+					.name("votes_election_index")
+					.table(Votes::Table)
+					.col(Votes::ElectionId)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		m20220101_000001_create_tables::Migration.down(manager).await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Election {
+	Table,
+	#[sea_orm(iden = "election_id")]
+	Id,
+	Name,
+	AdminId,
+	ResultId,
+}
+
+#[derive(DeriveIden)]
+enum Votes {
+	Table,
+	VoteId,
+	ElectionId,
+}
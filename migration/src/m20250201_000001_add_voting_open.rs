@@ -0,0 +1,41 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::Table,
+	schema,
+	sea_orm::{self, DeriveIden, DeriveMigrationName},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.add_column(schema::boolean(Election::VotingOpen).default(true))
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.drop_column(Election::VotingOpen)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Election {
+	Table,
+	VotingOpen,
+}
@@ -0,0 +1,233 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::{Index, Table},
+	schema,
+	sea_orm::{self, ColumnType, ConnectionTrait, DbBackend, DeriveIden, DeriveMigrationName, Statement},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+/// Replaces the Postgres array columns by a layout that works on every supported database:
+/// candidates get their own table, and each vote stores its ranking as text (e.g. `0>2=1>3`).
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(Candidate::Table)
+					.col(schema::string_len(Candidate::ElectionId, 16))
+					.col(schema::integer(Candidate::Position))
+					.col(schema::text(Candidate::Name))
+					.primary_key(
+						Index::create()
+							.col(Candidate::ElectionId)
+							.col(Candidate::Position),
+					)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Votes::Table)
+					.add_column(schema::text(Votes::Ranking).default(""))
+					.to_owned(),
+			)
+			.await?;
+
+		if manager.get_database_backend() != DbBackend::Postgres {
+			return Ok(());
+		}
+
+		// move the existing data out of the array columns
+		let db_conn = manager.get_connection();
+		db_conn
+			.execute_unprepared(
+				"INSERT INTO candidate (election_id, position, name) \
+				SELECT election_id, t.ord - 1, t.name FROM election, unnest(candidates) WITH ORDINALITY AS t(name, ord)",
+			)
+			.await?;
+		let votes = db_conn
+			.query_all(Statement::from_string(
+				DbBackend::Postgres,
+				"SELECT vote_id, cand_order, comp_is_bigger FROM votes",
+			))
+			.await?;
+		for vote in votes {
+			let vote_id: i32 = vote.try_get("", "vote_id")?;
+			let cand_order: Vec<i32> = vote.try_get("", "cand_order")?;
+			let comp_is_bigger: Vec<bool> = vote.try_get("", "comp_is_bigger")?;
+			db_conn
+				.execute(Statement::from_sql_and_values(
+					DbBackend::Postgres,
+					"UPDATE votes SET ranking = $1 WHERE vote_id = $2",
+					[encode_ranking(&cand_order, &comp_is_bigger).into(), vote_id.into()],
+				))
+				.await?;
+		}
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.drop_column(Election::Candidates)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Votes::Table)
+					.drop_column(Votes::CandOrder)
+					.drop_column(Votes::CompIsBigger)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		// only Postgres had the array columns to restore
+		if manager.get_database_backend() == DbBackend::Postgres {
+			manager
+				.alter_table(
+					Table::alter()
+						.table(Election::Table)
+						.add_column(schema::array_null(Election::Candidates, ColumnType::Text))
+						.to_owned(),
+				)
+				.await?;
+			manager
+				.alter_table(
+					Table::alter()
+						.table(Votes::Table)
+						.add_column(schema::array_null(Votes::CandOrder, ColumnType::Integer))
+						.add_column(schema::array_null(Votes::CompIsBigger, ColumnType::Boolean))
+						.to_owned(),
+				)
+				.await?;
+
+			// move the data back into the array columns
+			let db_conn = manager.get_connection();
+			db_conn
+				.execute_unprepared(
+					"UPDATE election SET candidates = ARRAY(\
+					SELECT name FROM candidate WHERE candidate.election_id = election.election_id ORDER BY position)",
+				)
+				.await?;
+			let votes = db_conn
+				.query_all(Statement::from_string(DbBackend::Postgres, "SELECT vote_id, ranking FROM votes"))
+				.await?;
+			for vote in votes {
+				let vote_id: i32 = vote.try_get("", "vote_id")?;
+				let ranking: String = vote.try_get("", "ranking")?;
+				let (cand_order, comp_is_bigger) = decode_ranking(&ranking)?;
+				db_conn
+					.execute(Statement::from_sql_and_values(
+						DbBackend::Postgres,
+						"UPDATE votes SET cand_order = $1, comp_is_bigger = $2 WHERE vote_id = $3",
+						[cand_order.into(), comp_is_bigger.into(), vote_id.into()],
+					))
+					.await?;
+			}
+
+			// the array columns were not nullable
+			manager
+				.alter_table(
+					Table::alter()
+						.table(Election::Table)
+						.modify_column(schema::array(Election::Candidates, ColumnType::Text))
+						.to_owned(),
+				)
+				.await?;
+			manager
+				.alter_table(
+					Table::alter()
+						.table(Votes::Table)
+						.modify_column(schema::array(Votes::CandOrder, ColumnType::Integer))
+						.modify_column(schema::array(Votes::CompIsBigger, ColumnType::Boolean))
+						.to_owned(),
+				)
+				.await?;
+		}
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Votes::Table)
+					.drop_column(Votes::Ranking)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.drop_table(Table::drop().table(Candidate::Table).to_owned())
+			.await
+	}
+}
+
+/// Same encoding as `Ballot::encode` in the main crate, with tied candidates in increasing order.
+fn encode_ranking(cand_order: &[i32], comp_is_bigger: &[bool]) -> String {
+	let mut groups: Vec<Vec<i32>> = Vec::new();
+	for (ind, candidate) in cand_order.iter().enumerate() {
+		if ind == 0 || comp_is_bigger.get(ind - 1).copied().unwrap_or(true) {
+			groups.push(Vec::new());
+		}
+		groups.last_mut().unwrap().push(*candidate);
+	}
+	groups
+		.into_iter()
+		.map(|mut group| {
+			group.sort_unstable();
+			group.iter().map(i32::to_string).collect::<Vec<_>>().join("=")
+		})
+		.collect::<Vec<_>>()
+		.join(">")
+}
+
+/// Inverse of `encode_ranking`: the candidates in order, and whether each one is preferred over the next.
+fn decode_ranking(ranking: &str) -> Result<(Vec<i32>, Vec<bool>), DbErr> {
+	let mut cand_order = Vec::new();
+	let mut comp_is_bigger = Vec::new();
+	if ranking.is_empty() {
+		return Ok((cand_order, comp_is_bigger));
+	}
+	for (group_ind, group) in ranking.split('>').enumerate() {
+		if group_ind > 0 {
+			comp_is_bigger.push(true);
+		}
+		for (ind, candidate) in group.split('=').enumerate() {
+			if ind > 0 {
+				comp_is_bigger.push(false);
+			}
+			let candidate = candidate
+				.parse()
+				.map_err(|_| DbErr::Migration(format!("invalid ranking '{}'", ranking)))?;
+			cand_order.push(candidate);
+		}
+	}
+	Ok((cand_order, comp_is_bigger))
+}
+
+#[derive(DeriveIden)]
+enum Election {
+	Table,
+	Candidates,
+}
+
+#[derive(DeriveIden)]
+enum Candidate {
+	Table,
+	ElectionId,
+	Position,
+	Name,
+}
+
+#[derive(DeriveIden)]
+enum Votes {
+	Table,
+	CandOrder,
+	CompIsBigger,
+	Ranking,
+}
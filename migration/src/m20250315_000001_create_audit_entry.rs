@@ -0,0 +1,52 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::{Index, Table},
+	schema,
+	sea_orm::{self, DeriveIden, DeriveMigrationName},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(AuditEntry::Table)
+					.col(schema::pk_auto(AuditEntry::Id))
+					.col(schema::string_len(AuditEntry::ElectionId, 16))
+					.col(schema::timestamp_with_time_zone(AuditEntry::CreatedAt))
+					.col(schema::text(AuditEntry::Action))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_index(
+				Index::create()
+					.name("audit_entry_election_index")
+					.table(AuditEntry::Table)
+					.col(AuditEntry::ElectionId)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(AuditEntry::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum AuditEntry {
+	Table,
+	#[sea_orm(iden = "audit_entry_id")]
+	Id,
+	ElectionId,
+	CreatedAt,
+	Action,
+}
@@ -0,0 +1,208 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::{Alias, Expr, Index, Query, Table},
+	schema,
+	sea_orm::{self, ConnectionTrait, DeriveIden, DeriveMigrationName},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+/// Adds the incrementally maintained pairwise tally, and fills it from the existing votes.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(PairwiseCount::Table)
+					.col(schema::string_len(PairwiseCount::ElectionId, 16))
+					.col(schema::integer(PairwiseCount::Winner))
+					.col(schema::integer(PairwiseCount::Loser))
+					.col(schema::big_integer(PairwiseCount::Count).default(0))
+					.primary_key(
+						Index::create()
+							.col(PairwiseCount::ElectionId)
+							.col(PairwiseCount::Winner)
+							.col(PairwiseCount::Loser),
+					)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.add_column(schema::big_integer(Election::BallotCount).default(0))
+					.to_owned(),
+			)
+			.await?;
+
+		// backfill the tally of existing elections
+		let db_conn = manager.get_connection();
+		let backend = manager.get_database_backend();
+		let elections = db_conn
+			.query_all(
+				backend.build(
+					Query::select()
+						.column(Candidate::ElectionId)
+						.expr_as(Expr::col(Candidate::Position).count(), Alias::new("n_candidates"))
+						.from(Candidate::Table)
+						.group_by_col(Candidate::ElectionId),
+				),
+			)
+			.await?;
+		for election in elections {
+			let election_id: String = election.try_get("", "election_id")?;
+			let n_candidates = election.try_get::<i64>("", "n_candidates")? as usize;
+			// group identical rankings in the database, so large elections are not loaded vote by vote
+			let rankings = db_conn
+				.query_all(
+					backend.build(
+						Query::select()
+							.column(Votes::Ranking)
+							.expr_as(Expr::col(Votes::VoteId).count(), Alias::new("n_votes"))
+							.from(Votes::Table)
+							.and_where(Expr::col(Votes::ElectionId).eq(election_id.clone()))
+							.group_by_col(Votes::Ranking),
+					),
+				)
+				.await?;
+			let mut counts = vec![vec![0i64; n_candidates]; n_candidates];
+			let mut n_votes_total = 0;
+			for ranking in rankings.iter() {
+				let n_votes: i64 = ranking.try_get("", "n_votes")?;
+				add_ranking(&mut counts, &ranking.try_get::<String>("", "ranking")?, n_votes)?;
+				n_votes_total += n_votes;
+			}
+			let pairs = (0..n_candidates)
+				.flat_map(|winner| (0..n_candidates).map(move |loser| (winner, loser)))
+				.filter(|(winner, loser)| winner != loser)
+				.collect::<Vec<_>>();
+			for chunk in pairs.chunks(500) {
+				let mut insert = Query::insert();
+				insert.into_table(PairwiseCount::Table).columns([
+					PairwiseCount::ElectionId,
+					PairwiseCount::Winner,
+					PairwiseCount::Loser,
+					PairwiseCount::Count,
+				]);
+				for (winner, loser) in chunk {
+					insert.values_panic([
+						election_id.clone().into(),
+						(*winner as i32).into(),
+						(*loser as i32).into(),
+						counts[*winner][*loser].into(),
+					]);
+				}
+				manager.exec_stmt(insert).await?;
+			}
+			manager
+				.exec_stmt(
+					Query::update()
+						.table(Election::Table)
+						.value(Election::BallotCount, n_votes_total)
+						.and_where(Expr::col(Election::Id).eq(election_id))
+						.to_owned(),
+				)
+				.await?;
+		}
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.drop_column(Election::BallotCount)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.drop_table(Table::drop().table(PairwiseCount::Table).to_owned())
+			.await
+	}
+}
+
+/// Adds a ranking in the text format of `Ballot::encode` in the main crate to the tally, `n_votes` times.
+fn add_ranking(counts: &mut [Vec<i64>], ranking: &str, n_votes: i64) -> Result<(), DbErr> {
+	if ranking.is_empty() {
+		return Ok(());
+	}
+	let mut groups = Vec::new();
+	for group in ranking.split('>') {
+		groups.push(
+			group
+				.split('=')
+				.map(|candidate| candidate.parse::<usize>())
+				.collect::<Result<Vec<_>, _>>()
+				.map_err(|_| DbErr::Custom(format!("Could not decode ranking {}", ranking)))?,
+		);
+	}
+	for (group_ind, group) in groups.iter().enumerate() {
+		for winner in group {
+			for loser in groups[(group_ind + 1)..].iter().flatten() {
+				let count = counts
+					.get_mut(*winner)
+					.and_then(|row| row.get_mut(*loser))
+					.ok_or_else(|| DbErr::Custom(format!("Ranking {} has an unknown candidate", ranking)))?;
+				*count += n_votes;
+			}
+		}
+	}
+	Ok(())
+}
+
+#[derive(DeriveIden)]
+enum Election {
+	Table,
+	#[sea_orm(iden = "election_id")]
+	Id,
+	BallotCount,
+}
+
+#[derive(DeriveIden)]
+enum Candidate {
+	Table,
+	ElectionId,
+	Position,
+}
+
+#[derive(DeriveIden)]
+enum Votes {
+	Table,
+	VoteId,
+	ElectionId,
+	Ranking,
+}
+
+#[derive(DeriveIden)]
+enum PairwiseCount {
+	Table,
+	ElectionId,
+	Winner,
+	Loser,
+	Count,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rankings_are_added_to_the_counts() {
+		let mut counts = vec![vec![0; 3]; 3];
+		add_ranking(&mut counts, "1>0=2", 2).unwrap();
+		assert_eq!(counts, vec![vec![0, 0, 0], vec![2, 0, 2], vec![0, 0, 0]]);
+	}
+
+	#[test]
+	fn unknown_candidates_are_errors() {
+		let mut counts = vec![vec![0; 3]; 3];
+		assert!(matches!(add_ranking(&mut counts, "0>3", 1), Err(DbErr::Custom(_))));
+		assert!(matches!(add_ranking(&mut counts, "3>0", 1), Err(DbErr::Custom(_))));
+		assert!(matches!(add_ranking(&mut counts, "0>a", 1), Err(DbErr::Custom(_))));
+	}
+}
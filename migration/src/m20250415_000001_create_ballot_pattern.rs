@@ -0,0 +1,73 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::{Expr, Index, Query, Table},
+	schema,
+	sea_orm::{self, DeriveIden, DeriveMigrationName},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+/// Adds the distinct ballots of each election with their number of voters, filled from the existing votes.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(BallotPattern::Table)
+					.col(schema::string_len(BallotPattern::ElectionId, 16))
+					.col(schema::text(BallotPattern::Ranking))
+					.col(schema::big_integer(BallotPattern::Count))
+					.primary_key(
+						Index::create()
+							.col(BallotPattern::ElectionId)
+							.col(BallotPattern::Ranking),
+					)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.exec_stmt(
+				Query::insert()
+					.into_table(BallotPattern::Table)
+					.columns([BallotPattern::ElectionId, BallotPattern::Ranking, BallotPattern::Count])
+					.select_from(
+						Query::select()
+							.column(Votes::ElectionId)
+							.column(Votes::Ranking)
+							.expr(Expr::col(Votes::VoteId).count())
+							.from(Votes::Table)
+							.group_by_col(Votes::ElectionId)
+							.group_by_col(Votes::Ranking)
+							.to_owned(),
+					)
+					.map_err(|error| DbErr::Custom(error.to_string()))?
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(BallotPattern::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Votes {
+	Table,
+	VoteId,
+	ElectionId,
+	Ranking,
+}
+
+#[derive(DeriveIden)]
+enum BallotPattern {
+	Table,
+	ElectionId,
+	Ranking,
+	Count,
+}
@@ -0,0 +1,71 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::{Index, Table},
+	schema,
+	sea_orm::{self, DeriveIden, DeriveMigrationName},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+/// Adds a version to each election that changes whenever its results may change,
+/// and a table to share computed results between server instances.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.add_column(schema::big_integer(Election::ResultsVersion).default(0))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_table(
+				Table::create()
+					.table(ResultsCache::Table)
+					.col(schema::string_len(ResultsCache::ElectionId, 16))
+					.col(schema::string(ResultsCache::Configuration))
+					.col(schema::big_integer(ResultsCache::ResultsVersion))
+					.col(schema::text(ResultsCache::Results))
+					.primary_key(
+						Index::create()
+							.col(ResultsCache::ElectionId)
+							.col(ResultsCache::Configuration),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(ResultsCache::Table).to_owned())
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.drop_column(Election::ResultsVersion)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Election {
+	Table,
+	ResultsVersion,
+}
+
+#[derive(DeriveIden)]
+enum ResultsCache {
+	Table,
+	ElectionId,
+	Configuration,
+	ResultsVersion,
+	Results,
+}
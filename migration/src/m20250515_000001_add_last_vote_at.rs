@@ -0,0 +1,42 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::Table,
+	schema,
+	sea_orm::{self, DeriveIden, DeriveMigrationName},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+/// Adds the time of the latest ballot to each election, for the live vote counter of the admin page.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.add_column(schema::timestamp_with_time_zone_null(Election::LastVoteAt))
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.drop_column(Election::LastVoteAt)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Election {
+	Table,
+	LastVoteAt,
+}
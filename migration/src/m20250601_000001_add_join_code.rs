@@ -0,0 +1,61 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::{Index, Table},
+	schema,
+	sea_orm::{self, DeriveIden, DeriveMigrationName},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+/// Adds the optional short join code of open elections. The unique index keeps codes unambiguous,
+/// while elections without a code can all leave it empty.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.add_column(schema::string_len_null(Election::JoinCode, 6))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_index(
+				Index::create()
+					.name("idx-election-join_code")
+					.table(Election::Table)
+					.col(Election::JoinCode)
+					.unique()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(
+				Index::drop()
+					.name("idx-election-join_code")
+					.table(Election::Table)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.drop_column(Election::JoinCode)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Election {
+	Table,
+	JoinCode,
+}
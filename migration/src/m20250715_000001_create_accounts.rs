@@ -0,0 +1,137 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::{Index, Table},
+	schema,
+	sea_orm::{self, DeriveIden, DeriveMigrationName},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+/// Adds the optional organizer accounts, with their passkeys and login sessions,
+/// and the account that owns an election, if it was created while logged in.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(Account::Table)
+					.col(schema::string_len(Account::Id, 36).primary_key())
+					.col(schema::string_uniq(Account::Username))
+					.col(schema::text_null(Account::PasswordHash))
+					.col(schema::timestamp_with_time_zone(Account::CreatedAt))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_table(
+				Table::create()
+					.table(Passkey::Table)
+					.col(schema::string(Passkey::CredentialId).primary_key())
+					.col(schema::string_len(Passkey::AccountId, 36))
+					.col(schema::text(Passkey::Data))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_index(
+				Index::create()
+					.name("idx-passkey-account_id")
+					.table(Passkey::Table)
+					.col(Passkey::AccountId)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_table(
+				Table::create()
+					.table(Session::Table)
+					.col(schema::string_len(Session::Id, 32).primary_key())
+					.col(schema::string_len(Session::AccountId, 36))
+					.col(schema::timestamp_with_time_zone(Session::ExpiresAt))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.add_column(schema::string_len_null(Election::OwnerAccountId, 36))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_index(
+				Index::create()
+					.name("idx-election-owner_account_id")
+					.table(Election::Table)
+					.col(Election::OwnerAccountId)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(
+				Index::drop()
+					.name("idx-election-owner_account_id")
+					.table(Election::Table)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.drop_column(Election::OwnerAccountId)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.drop_table(Table::drop().table(Session::Table).to_owned())
+			.await?;
+		manager
+			.drop_table(Table::drop().table(Passkey::Table).to_owned())
+			.await?;
+		manager
+			.drop_table(Table::drop().table(Account::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Account {
+	Table,
+	#[sea_orm(iden = "account_id")]
+	Id,
+	Username,
+	PasswordHash,
+	CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Passkey {
+	Table,
+	CredentialId,
+	AccountId,
+	#[sea_orm(iden = "passkey")]
+	Data,
+}
+
+#[derive(DeriveIden)]
+enum Session {
+	Table,
+	#[sea_orm(iden = "session_id")]
+	Id,
+	AccountId,
+	ExpiresAt,
+}
+
+#[derive(DeriveIden)]
+enum Election {
+	Table,
+	OwnerAccountId,
+}
@@ -0,0 +1,125 @@
+use sea_orm_migration::{
+	async_trait,
+	prelude::{Index, Table},
+	schema,
+	sea_orm::{self, DeriveIden, DeriveMigrationName},
+	DbErr, MigrationTrait, SchemaManager,
+};
+
+/// Links accounts to the subject they log in as through OpenID Connect, and whether that subject is
+/// in the voter group. Members-only elections record who voted in them, separately from the ballots.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		// SQLite alters one column at a time, and cannot add a unique column, hence the separate index
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Account::Table)
+					.add_column(schema::string_null(Account::OidcSubject))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Account::Table)
+					.add_column(schema::boolean(Account::OidcMember).default(false))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_index(
+				Index::create()
+					.name("idx-account-oidc_subject")
+					.table(Account::Table)
+					.col(Account::OidcSubject)
+					.unique()
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.add_column(schema::boolean(Election::MembersOnly).default(false))
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_table(
+				Table::create()
+					.table(ElectionVoter::Table)
+					.col(schema::string_len(ElectionVoter::ElectionId, 16))
+					.col(schema::string_len(ElectionVoter::AccountId, 36))
+					.primary_key(
+						Index::create()
+							.col(ElectionVoter::ElectionId)
+							.col(ElectionVoter::AccountId),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(ElectionVoter::Table).to_owned())
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Election::Table)
+					.drop_column(Election::MembersOnly)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.drop_index(
+				Index::drop()
+					.name("idx-account-oidc_subject")
+					.table(Account::Table)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Account::Table)
+					.drop_column(Account::OidcMember)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Account::Table)
+					.drop_column(Account::OidcSubject)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Account {
+	Table,
+	OidcSubject,
+	OidcMember,
+}
+
+#[derive(DeriveIden)]
+enum Election {
+	Table,
+	MembersOnly,
+}
+
+#[derive(DeriveIden)]
+enum ElectionVoter {
+	Table,
+	ElectionId,
+	AccountId,
+}
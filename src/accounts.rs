@@ -0,0 +1,480 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use chrono::Utc;
+use http::HeaderMap;
+use nanoid::nanoid;
+use webauthn_rs::prelude::{
+	CreationChallengeResponse, CredentialID, Passkey, PasskeyAuthentication, PasskeyRegistration,
+	PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse, Url, Uuid, Webauthn,
+	WebauthnBuilder,
+};
+
+use crate::endpoints::{AccountElection, AccountInfo};
+use crate::errors::FriendVoteError;
+use crate::rate_limit::RateLimiter;
+use crate::store::{Account, ElectionStore};
+
+/// Name of the cookie that holds the session of a logged in organizer.
+const SESSION_COOKIE: &str = "friendvote_session";
+const SESSION_LIFETIME_DAYS: i64 = 30;
+const MAX_USERNAME_LENGTH: usize = 64;
+const MIN_PASSWORD_LENGTH: usize = 8;
+/// Checked instead when the username is unknown, so that the answer takes as long as for a wrong password.
+static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| {
+	let salt = SaltString::generate(&mut rand::rngs::OsRng);
+	Argon2::default()
+		.hash_password(b"not a password", &salt)
+		.expect("could not hash the dummy password")
+		.to_string()
+});
+
+/// Creates an account with a password, and returns its id.
+pub async fn sign_up(
+	store: &dyn ElectionStore,
+	username: &str,
+	password: &str,
+) -> Result<String, FriendVoteError> {
+	let username = username.trim();
+	if username.is_empty() || MAX_USERNAME_LENGTH < username.chars().count() {
+		return Err(FriendVoteError::Validation(format!(
+			"Usernames have between 1 and {} characters.",
+			MAX_USERNAME_LENGTH
+		)));
+	}
+	if password.chars().count() < MIN_PASSWORD_LENGTH {
+		return Err(FriendVoteError::Validation(format!(
+			"Passwords have at least {} characters.",
+			MIN_PASSWORD_LENGTH
+		)));
+	}
+	// the account id doubles as the WebAuthn user handle, which must not reveal the username
+	let account_id = Uuid::new_v4().to_string();
+	let inserted = store
+		.insert_account(Account {
+			account_id: account_id.clone(),
+			username: username.to_string(),
+			password_hash: Some(hash_password(password.to_string()).await?),
+			oidc_subject: None,
+			oidc_member: false,
+		})
+		.await?;
+	if !inserted {
+		return Err(FriendVoteError::Validation("This username is taken.".to_string()));
+	}
+	Ok(account_id)
+}
+
+/// Checks the password of an account, and returns its id. Every attempt of `client` counts,
+/// so that passwords cannot be guessed by trying many.
+pub async fn log_in(
+	store: &dyn ElectionStore,
+	limiter: &RateLimiter,
+	client: IpAddr,
+	username: &str,
+	password: &str,
+) -> Result<String, FriendVoteError> {
+	limiter.check(client)?;
+	let (account_id, password_hash) = match store.find_account_by_username(username.trim()).await? {
+		Some(Account {
+			account_id,
+			password_hash: Some(password_hash),
+			..
+		}) => (Some(account_id), Some(password_hash)),
+		_ => (None, None),
+	};
+	// an unknown username fails like a wrong password, and just as slowly
+	let verified = verify_password(password.to_string(), password_hash).await?;
+	account_id
+		.filter(|_| verified)
+		.ok_or_else(|| FriendVoteError::Forbidden("Wrong username or password.".to_string()))
+}
+
+/// Argon2 is deliberately slow, so it runs off the async worker threads.
+async fn hash_password(password: String) -> Result<String, FriendVoteError> {
+	tokio::task::spawn_blocking(move || {
+		let salt = SaltString::generate(&mut rand::rngs::OsRng);
+		Argon2::default()
+			.hash_password(password.as_bytes(), &salt)
+			.map(|hash| hash.to_string())
+	})
+	.await
+	.map_err(|error| FriendVoteError::Internal(error.to_string()))?
+	.map_err(|error| FriendVoteError::Internal(format!("Could not hash the password: {}", error)))
+}
+
+/// Without a `password_hash`, the password is checked against a dummy hash, which always fails.
+async fn verify_password(password: String, password_hash: Option<String>) -> Result<bool, FriendVoteError> {
+	tokio::task::spawn_blocking(move || {
+		let known = password_hash.is_some();
+		let password_hash = password_hash.unwrap_or_else(|| DUMMY_PASSWORD_HASH.clone());
+		let password_hash = PasswordHash::new(&password_hash)
+			.map_err(|error| FriendVoteError::Internal(format!("Invalid stored password hash: {}", error)))?;
+		let verified = Argon2::default()
+			.verify_password(password.as_bytes(), &password_hash)
+			.is_ok();
+		Ok(known && verified)
+	})
+	.await
+	.map_err(|error| FriendVoteError::Internal(error.to_string()))?
+}
+
+/// Starts a session for the account, and returns the `Set-Cookie` header value that logs the browser in.
+pub async fn start_session(
+	store: &dyn ElectionStore,
+	account_id: &str,
+	secure: bool,
+) -> Result<String, FriendVoteError> {
+	let session_id = nanoid!(32);
+	let expires_at = Utc::now() + chrono::Duration::days(SESSION_LIFETIME_DAYS);
+	store.insert_session(&session_id, account_id, expires_at).await?;
+	Ok(session_cookie(&session_id, SESSION_LIFETIME_DAYS * 24 * 60 * 60, secure))
+}
+
+/// Ends the session in the request headers, if any, and returns the `Set-Cookie` header value that forgets it.
+pub async fn end_session(
+	store: &dyn ElectionStore,
+	headers: &HeaderMap,
+	secure: bool,
+) -> Result<String, FriendVoteError> {
+	if let Some(session_id) = session_id(headers) {
+		store.delete_session(&session_id).await?;
+	}
+	Ok(session_cookie("", 0, secure))
+}
+
+/// The cookie is out of reach of scripts, and not sent along with requests that other sites make.
+fn session_cookie(session_id: &str, max_age_seconds: i64, secure: bool) -> String {
+	let cookie = format!(
+		"{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+		SESSION_COOKIE, session_id, max_age_seconds
+	);
+	if secure {
+		cookie + "; Secure"
+	} else {
+		cookie
+	}
+}
+
+fn session_id(headers: &HeaderMap) -> Option<String> {
+	cookie_value(headers, SESSION_COOKIE)
+}
+
+/// Returns the value of the cookie named `name` in the request headers, unless it is empty.
+pub(crate) fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+	headers
+		.get_all(http::header::COOKIE)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.flat_map(|value| value.split(';'))
+		.filter_map(|cookie| cookie.trim().split_once('='))
+		.find(|(cookie_name, _)| *cookie_name == name)
+		.map(|(_, value)| value.to_string())
+		.filter(|value| !value.is_empty())
+}
+
+/// Returns the account logged in through the session cookie in the request headers, if any.
+pub async fn session_account_id(
+	store: &dyn ElectionStore,
+	headers: &HeaderMap,
+) -> Result<Option<String>, FriendVoteError> {
+	match session_id(headers) {
+		Some(session_id) => store.get_session_account(&session_id).await,
+		None => Ok(None),
+	}
+}
+
+pub async fn account_info(
+	store: &dyn ElectionStore,
+	account_id: &str,
+) -> Result<AccountInfo, FriendVoteError> {
+	let account = store.get_account(account_id).await?;
+	let n_passkeys = store.get_passkeys(account_id).await?.len();
+	let elections = store.get_owned_elections(account_id).await?;
+	Ok(AccountInfo {
+		username: account.username,
+		n_passkeys,
+		elections: elections
+			.into_iter()
+			.map(|election| AccountElection {
+				admin_path: crate::elections::owner_admin_path(&election.election_id),
+				name: election.name,
+			})
+			.collect(),
+	})
+}
+
+/// Shared by the server functions, like the store.
+pub type Passkeys = Arc<PasskeyVerifier>;
+
+/// Passkey ceremonies that are not finished within this time are abandoned.
+const CEREMONY_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+enum Ceremony {
+	Registration {
+		account_id: String,
+		state: PasskeyRegistration,
+	},
+	Login {
+		account_id: String,
+		state: PasskeyAuthentication,
+	},
+}
+
+/// Registers passkeys and logs in with them. The state of a ceremony stays in memory, under an id that the
+/// browser sends back with the signed challenge, so a ceremony must finish on the instance that started it.
+pub struct PasskeyVerifier {
+	webauthn: Webauthn,
+	/// When each ceremony started, and its state.
+	ceremonies: Mutex<HashMap<String, (Instant, Ceremony)>>,
+}
+
+impl PasskeyVerifier {
+	/// Passkeys are bound to the host of `origin`, the address at which users reach the site.
+	pub fn new(origin: &Url) -> Result<Self, FriendVoteError> {
+		let rp_id = origin
+			.host_str()
+			.ok_or_else(|| FriendVoteError::Internal(format!("{} has no host", origin)))?;
+		let webauthn = WebauthnBuilder::new(rp_id, origin)
+			.and_then(|builder| builder.rp_name("FriendVote").build())
+			.map_err(|error| FriendVoteError::Internal(format!("Could not set up passkeys: {}", error)))?;
+		Ok(Self {
+			webauthn,
+			ceremonies: Mutex::default(),
+		})
+	}
+
+	fn begin(&self, ceremony: Ceremony) -> String {
+		let now = Instant::now();
+		let mut ceremonies = self.ceremonies.lock().expect("passkey ceremonies lock poisoned");
+		ceremonies.retain(|_, (started, _)| now.duration_since(*started) < CEREMONY_TIMEOUT);
+		let ceremony_id = nanoid!(32);
+		ceremonies.insert(ceremony_id.clone(), (now, ceremony));
+		ceremony_id
+	}
+
+	/// A ceremony can be finished only once, whether it succeeds or not.
+	fn take(&self, ceremony_id: &str) -> Result<Ceremony, FriendVoteError> {
+		let mut ceremonies = self.ceremonies.lock().expect("passkey ceremonies lock poisoned");
+		ceremonies
+			.remove(ceremony_id)
+			.filter(|(started, _)| started.elapsed() < CEREMONY_TIMEOUT)
+			.map(|(_, ceremony)| ceremony)
+			.ok_or_else(ceremony_expired)
+	}
+
+	pub async fn start_registration(
+		&self,
+		store: &dyn ElectionStore,
+		account_id: &str,
+	) -> Result<(String, CreationChallengeResponse), FriendVoteError> {
+		let account = store.get_account(account_id).await?;
+		let user_id = Uuid::parse_str(account_id)
+			.map_err(|error| FriendVoteError::Internal(format!("Invalid account id: {}", error)))?;
+		// so that an authenticator does not register the same account twice
+		let registered = stored_passkeys(store, account_id)
+			.await?
+			.iter()
+			.map(|passkey| passkey.cred_id().clone())
+			.collect();
+		let (options, state) = self
+			.webauthn
+			.start_passkey_registration(user_id, &account.username, &account.username, Some(registered))
+			.map_err(|error| {
+				FriendVoteError::Internal(format!("Could not start the registration: {}", error))
+			})?;
+		let ceremony_id = self.begin(Ceremony::Registration {
+			account_id: account_id.to_string(),
+			state,
+		});
+		Ok((ceremony_id, options))
+	}
+
+	pub async fn finish_registration(
+		&self,
+		store: &dyn ElectionStore,
+		account_id: &str,
+		ceremony_id: &str,
+		credential: &RegisterPublicKeyCredential,
+	) -> Result<(), FriendVoteError> {
+		let Ceremony::Registration {
+			account_id: started_by,
+			state,
+		} = self.take(ceremony_id)?
+		else {
+			return Err(ceremony_expired());
+		};
+		if started_by != account_id {
+			return Err(ceremony_expired());
+		}
+		let passkey = self
+			.webauthn
+			.finish_passkey_registration(credential, &state)
+			.map_err(|error| {
+				FriendVoteError::Forbidden(format!("The passkey could not be verified: {}", error))
+			})?;
+		store
+			.insert_passkey(account_id, &credential_key(passkey.cred_id()), &serialize_passkey(&passkey)?)
+			.await
+	}
+
+	/// Every attempt of `client` counts, like password logins.
+	pub async fn start_login(
+		&self,
+		store: &dyn ElectionStore,
+		limiter: &RateLimiter,
+		client: IpAddr,
+		username: &str,
+	) -> Result<(String, RequestChallengeResponse), FriendVoteError> {
+		limiter.check(client)?;
+		// the same answer whether the account does not exist or has no passkeys
+		let cannot_log_in = || FriendVoteError::Forbidden("Could not log in with a passkey.".to_string());
+		let account = store
+			.find_account_by_username(username.trim())
+			.await?
+			.ok_or_else(cannot_log_in)?;
+		let passkeys = stored_passkeys(store, &account.account_id).await?;
+		if passkeys.is_empty() {
+			return Err(cannot_log_in());
+		}
+		let (options, state) = self
+			.webauthn
+			.start_passkey_authentication(&passkeys)
+			.map_err(|error| FriendVoteError::Internal(format!("Could not start the login: {}", error)))?;
+		let ceremony_id = self.begin(Ceremony::Login {
+			account_id: account.account_id,
+			state,
+		});
+		Ok((ceremony_id, options))
+	}
+
+	/// Returns the id of the account that logged in.
+	pub async fn finish_login(
+		&self,
+		store: &dyn ElectionStore,
+		ceremony_id: &str,
+		credential: &PublicKeyCredential,
+	) -> Result<String, FriendVoteError> {
+		let Ceremony::Login { account_id, state } = self.take(ceremony_id)? else {
+			return Err(ceremony_expired());
+		};
+		let result = self
+			.webauthn
+			.finish_passkey_authentication(credential, &state)
+			.map_err(|error| {
+				FriendVoteError::Forbidden(format!("The passkey could not be verified: {}", error))
+			})?;
+		if result.needs_update() {
+			// the signature counter reveals cloned authenticators, so it is kept up to date
+			let mut passkeys = stored_passkeys(store, &account_id).await?;
+			if let Some(passkey) = passkeys
+				.iter_mut()
+				.find(|passkey| passkey.cred_id() == result.cred_id())
+			{
+				passkey.update_credential(&result);
+				store
+					.update_passkey(&credential_key(passkey.cred_id()), &serialize_passkey(passkey)?)
+					.await?;
+			}
+		}
+		Ok(account_id)
+	}
+}
+
+fn ceremony_expired() -> FriendVoteError {
+	FriendVoteError::Forbidden("The passkey request expired, please try again.".to_string())
+}
+
+fn credential_key(credential_id: &CredentialID) -> String {
+	URL_SAFE_NO_PAD.encode(credential_id)
+}
+
+fn serialize_passkey(passkey: &Passkey) -> Result<String, FriendVoteError> {
+	serde_json::to_string(passkey).map_err(|error| FriendVoteError::Internal(error.to_string()))
+}
+
+async fn stored_passkeys(
+	store: &dyn ElectionStore,
+	account_id: &str,
+) -> Result<Vec<Passkey>, FriendVoteError> {
+	store
+		.get_passkeys(account_id)
+		.await?
+		.iter()
+		.map(|passkey| {
+			serde_json::from_str(passkey).map_err(|error| FriendVoteError::Internal(error.to_string()))
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::{IpAddr, Ipv4Addr};
+
+	use super::*;
+	use crate::store::MemoryStore;
+
+	const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+	#[tokio::test]
+	async fn unknown_usernames_fail_like_wrong_passwords() {
+		let store = MemoryStore::new();
+		let limiter = RateLimiter::new(10, Duration::from_secs(60));
+		let account_id = sign_up(&store, "alice", "correct horse").await.unwrap();
+
+		assert_eq!(
+			log_in(&store, &limiter, CLIENT, " alice ", "correct horse")
+				.await
+				.unwrap(),
+			account_id
+		);
+		let wrong_password = log_in(&store, &limiter, CLIENT, "alice", "wrong horse")
+			.await
+			.unwrap_err();
+		let unknown_username = log_in(&store, &limiter, CLIENT, "bob", "correct horse")
+			.await
+			.unwrap_err();
+		assert!(matches!(wrong_password, FriendVoteError::Forbidden(_)));
+		assert_eq!(wrong_password, unknown_username);
+	}
+
+	#[tokio::test]
+	async fn logins_are_rate_limited() {
+		let store = MemoryStore::new();
+		let limiter = RateLimiter::new(2, Duration::from_secs(60));
+		sign_up(&store, "alice", "correct horse").await.unwrap();
+
+		for _ in 0..2 {
+			let error = log_in(&store, &limiter, CLIENT, "alice", "wrong horse")
+				.await
+				.unwrap_err();
+			assert!(matches!(error, FriendVoteError::Forbidden(_)));
+		}
+		// not even the right password gets through until the window has passed
+		let error = log_in(&store, &limiter, CLIENT, "alice", "correct horse")
+			.await
+			.unwrap_err();
+		assert!(matches!(error, FriendVoteError::TooManyRequests(_)));
+		// while other clients, such as others behind the same proxy, still can log in
+		let other_client = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2));
+		log_in(&store, &limiter, other_client, "alice", "correct horse")
+			.await
+			.unwrap();
+	}
+}
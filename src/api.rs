@@ -0,0 +1,597 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use futures::Stream;
+use tokio::sync::broadcast::{self, error::RecvError};
+use utoipa::OpenApi;
+
+use crate::elections;
+use crate::endpoints::{AdminInfo, AuditLogEntry, BallotFrequency, ElectionResults, VoteCount, VotingInfo};
+use crate::errors::FriendVoteError;
+use crate::rate_limit::{JoinCodeLimiter, TrustedProxy};
+use crate::store::{Store, VoteSummary};
+
+#[derive(OpenApi)]
+#[openapi(
+	info(title = "FriendVote API", description = "Create ranked-choice elections, vote and read the results."),
+	paths(
+		create_election,
+		get_election,
+		cast_vote,
+		get_results,
+		get_admin_info,
+		close_voting,
+		reopen_voting,
+		vote_count_events,
+		result_vote_count_events,
+		create_join_code,
+		join_election
+	),
+	components(schemas(
+		AuditLogEntry,
+		CreateElectionRequest,
+		CreatedElection,
+		BallotRequest,
+		ApiErrorBody,
+		AdminInfo,
+		VotingInfo,
+		ElectionResults,
+		BallotFrequency,
+		VoteCount,
+		JoinCode,
+		JoinedElection
+	)),
+	tags((name = "elections", description = "Election management and voting"))
+)]
+pub struct ApiDoc;
+
+/// Builds the `/api/v1` router.
+pub fn router<S>(store: Store, join_code_limiter: JoinCodeLimiter, trusted_proxy: TrustedProxy) -> Router<S>
+where
+	S: Clone + Send + Sync + 'static,
+{
+	Router::new()
+		.route("/openapi.json", get(openapi))
+		.route("/elections", post(create_election))
+		.route("/elections/:election_id", get(get_election))
+		.route("/elections/:election_id/votes", post(cast_vote))
+		.route("/elections/:election_id/results/:result_id", get(get_results))
+		.route("/elections/:election_id/results/:result_id/votes/events", get(result_vote_count_events))
+		.route("/elections/:election_id/admin/:admin_id", get(get_admin_info))
+		.route("/elections/:election_id/admin/:admin_id/close", post(close_voting))
+		.route("/elections/:election_id/admin/:admin_id/reopen", post(reopen_voting))
+		.route("/elections/:election_id/admin/:admin_id/votes/events", get(vote_count_events))
+		.route("/elections/:election_id/admin/:admin_id/join_code", post(create_join_code))
+		.route("/join/:join_code", get(join_election))
+		.layer(Extension(join_code_limiter))
+		.layer(Extension(trusted_proxy))
+		.with_state(store)
+}
+
+/// Body of all error responses.
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct ApiErrorBody {
+	pub error: String,
+}
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+	Json(ApiDoc::openapi())
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct CreateElectionRequest {
+	/// Name of the election
+	pub name: String,
+	/// Candidate names, in the order in which they are shown to voters
+	pub candidates: Vec<String>,
+	/// Show the candidates to each voter in a new random order instead
+	#[serde(default)]
+	pub shuffle_candidates: bool,
+	/// Ask voters on the vote page to confirm their ranking, written out in words, before it is submitted
+	#[serde(default)]
+	pub review_ballots: bool,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct CreatedElection {
+	pub election_id: String,
+	/// Secret token granting access to the admin endpoints
+	pub admin_id: String,
+	/// Secret token granting access to the results
+	pub result_id: String,
+	pub admin_path: String,
+	pub vote_path: String,
+	pub result_path: String,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/elections",
+	tag = "elections",
+	request_body = CreateElectionRequest,
+	responses(
+		(status = 201, description = "Election created", body = CreatedElection),
+		(status = 422, description = "Invalid election", body = ApiErrorBody),
+		(status = 500, description = "Internal error", body = ApiErrorBody),
+	)
+)]
+async fn create_election(
+	State(store): State<Store>,
+	Json(request): Json<CreateElectionRequest>,
+) -> Result<(StatusCode, Json<CreatedElection>), FriendVoteError> {
+	let new_election = elections::create_election(
+		store.as_ref(),
+		&request.name,
+		request.candidates,
+		request.shuffle_candidates,
+		request.review_ballots,
+		// API clients authenticate with the admin token, so their elections have no owning account
+		None,
+		// nor can they vote as a logged in member
+		false,
+	)
+	.await?;
+	Ok((
+		StatusCode::CREATED,
+		Json(CreatedElection {
+			admin_path: elections::admin_path(&new_election.election_id, &new_election.admin_id),
+			vote_path: elections::vote_path(&new_election.election_id),
+			result_path: elections::result_path(&new_election.election_id, &new_election.result_id),
+			election_id: new_election.election_id,
+			admin_id: new_election.admin_id,
+			result_id: new_election.result_id,
+		}),
+	))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/elections/{election_id}",
+	tag = "elections",
+	params(("election_id" = String, Path, description = "Election id")),
+	responses(
+		(status = 200, description = "Election name and candidates", body = VotingInfo),
+		(status = 404, description = "Unknown election", body = ApiErrorBody),
+	)
+)]
+async fn get_election(
+	State(store): State<Store>,
+	Path(election_id): Path<String>,
+) -> Result<Json<VotingInfo>, FriendVoteError> {
+	Ok(Json(elections::vote_info(store.as_ref(), &election_id).await?))
+}
+
+/// A ranking of all candidates, from most to least preferred.
+/// Each entry is a group of equally ranked candidates.
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct BallotRequest {
+	pub ranking: Vec<Vec<String>>,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/elections/{election_id}/votes",
+	tag = "elections",
+	params(("election_id" = String, Path, description = "Election id")),
+	request_body = BallotRequest,
+	responses(
+		(status = 201, description = "Vote stored"),
+		(status = 403, description = "Only logged in members can vote in this election", body = ApiErrorBody),
+		(status = 404, description = "Unknown election", body = ApiErrorBody),
+		(status = 409, description = "Voting is closed", body = ApiErrorBody),
+		(status = 422, description = "Invalid ballot", body = ApiErrorBody),
+	)
+)]
+async fn cast_vote(
+	State(store): State<Store>,
+	Path(election_id): Path<String>,
+	Json(request): Json<BallotRequest>,
+) -> Result<StatusCode, FriendVoteError> {
+	let mut candidates = Vec::new();
+	let mut comparator_is_bigger = Vec::new();
+	for group in request.ranking.into_iter().filter(|group| !group.is_empty()) {
+		if !candidates.is_empty() {
+			comparator_is_bigger.push(true);
+		}
+		for (ind, candidate) in group.into_iter().enumerate() {
+			if ind > 0 {
+				comparator_is_bigger.push(false);
+			}
+			candidates.push(candidate);
+		}
+	}
+	elections::cast_vote(store.as_ref(), &election_id, candidates, comparator_is_bigger, None).await?;
+	Ok(StatusCode::CREATED)
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/elections/{election_id}/results/{result_id}",
+	tag = "elections",
+	params(
+		("election_id" = String, Path, description = "Election id"),
+		("result_id" = String, Path, description = "Result token"),
+	),
+	responses(
+		(status = 200, description = "Defeats matrix and global ranking", body = ElectionResults),
+		(status = 403, description = "Wrong result token", body = ApiErrorBody),
+		(status = 404, description = "Unknown election", body = ApiErrorBody),
+	)
+)]
+async fn get_results(
+	State(store): State<Store>,
+	Path((election_id, result_id)): Path<(String, String)>,
+) -> Result<Json<ElectionResults>, FriendVoteError> {
+	Ok(Json(elections::election_results(store.as_ref(), &election_id, &result_id).await?))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/elections/{election_id}/admin/{admin_id}",
+	tag = "elections",
+	params(
+		("election_id" = String, Path, description = "Election id"),
+		("admin_id" = String, Path, description = "Admin token"),
+	),
+	responses(
+		(status = 200, description = "Election links and state", body = AdminInfo),
+		(status = 403, description = "Wrong admin token", body = ApiErrorBody),
+		(status = 404, description = "Unknown election", body = ApiErrorBody),
+	)
+)]
+async fn get_admin_info(
+	State(store): State<Store>,
+	Path((election_id, admin_id)): Path<(String, String)>,
+) -> Result<Json<AdminInfo>, FriendVoteError> {
+	Ok(Json(elections::admin_info(store.as_ref(), &election_id, &admin_id).await?))
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/elections/{election_id}/admin/{admin_id}/close",
+	tag = "elections",
+	params(
+		("election_id" = String, Path, description = "Election id"),
+		("admin_id" = String, Path, description = "Admin token"),
+	),
+	responses(
+		(status = 204, description = "Voting closed"),
+		(status = 403, description = "Wrong admin token", body = ApiErrorBody),
+		(status = 404, description = "Unknown election", body = ApiErrorBody),
+	)
+)]
+async fn close_voting(
+	State(store): State<Store>,
+	Path((election_id, admin_id)): Path<(String, String)>,
+) -> Result<StatusCode, FriendVoteError> {
+	elections::update_voting_open(store.as_ref(), &election_id, &admin_id, false).await?;
+	Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/elections/{election_id}/admin/{admin_id}/reopen",
+	tag = "elections",
+	params(
+		("election_id" = String, Path, description = "Election id"),
+		("admin_id" = String, Path, description = "Admin token"),
+	),
+	responses(
+		(status = 204, description = "Voting reopened"),
+		(status = 403, description = "Wrong admin token", body = ApiErrorBody),
+		(status = 404, description = "Unknown election", body = ApiErrorBody),
+	)
+)]
+async fn reopen_voting(
+	State(store): State<Store>,
+	Path((election_id, admin_id)): Path<(String, String)>,
+) -> Result<StatusCode, FriendVoteError> {
+	elections::update_voting_open(store.as_ref(), &election_id, &admin_id, true).await?;
+	Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct JoinCode {
+	/// Six characters that voters can type on the home page instead of following the vote link
+	pub join_code: String,
+}
+
+#[utoipa::path(
+	post,
+	path = "/api/v1/elections/{election_id}/admin/{admin_id}/join_code",
+	tag = "elections",
+	params(
+		("election_id" = String, Path, description = "Election id"),
+		("admin_id" = String, Path, description = "Admin token"),
+	),
+	responses(
+		(status = 200, description = "The join code, which is freed when voting closes", body = JoinCode),
+		(status = 403, description = "Wrong admin token", body = ApiErrorBody),
+		(status = 404, description = "Unknown election", body = ApiErrorBody),
+		(status = 409, description = "Voting is closed", body = ApiErrorBody),
+	)
+)]
+async fn create_join_code(
+	State(store): State<Store>,
+	Path((election_id, admin_id)): Path<(String, String)>,
+) -> Result<Json<JoinCode>, FriendVoteError> {
+	let join_code = elections::create_join_code(store.as_ref(), &election_id, &admin_id).await?;
+	Ok(Json(JoinCode { join_code }))
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct JoinedElection {
+	pub election_id: String,
+	pub vote_path: String,
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/join/{join_code}",
+	tag = "elections",
+	params(("join_code" = String, Path, description = "Join code, case insensitive")),
+	responses(
+		(status = 200, description = "The election with this join code", body = JoinedElection),
+		(status = 404, description = "No open election has this join code", body = ApiErrorBody),
+		(status = 422, description = "Malformed join code", body = ApiErrorBody),
+		(status = 429, description = "Too many attempts from this address", body = ApiErrorBody),
+	)
+)]
+async fn join_election(
+	State(store): State<Store>,
+	Extension(limiter): Extension<JoinCodeLimiter>,
+	Extension(trusted_proxy): Extension<TrustedProxy>,
+	ConnectInfo(peer): ConnectInfo<SocketAddr>,
+	headers: HeaderMap,
+	Path(join_code): Path<String>,
+) -> Result<Json<JoinedElection>, FriendVoteError> {
+	let client = trusted_proxy.client_address(peer, &headers);
+	let election_id = elections::resolve_join_code(store.as_ref(), &limiter, client, &join_code).await?;
+	Ok(Json(JoinedElection {
+		vote_path: elections::vote_path(&election_id),
+		election_id,
+	}))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/elections/{election_id}/admin/{admin_id}/votes/events",
+	tag = "elections",
+	params(
+		("election_id" = String, Path, description = "Election id"),
+		("admin_id" = String, Path, description = "Admin token"),
+	),
+	responses(
+		(
+			status = 200,
+			description = "Server-sent events, each holding the vote count as JSON. \
+				The first event holds the current count, the next ones follow every new ballot.",
+			content_type = "text/event-stream",
+			body = VoteCount,
+		),
+		(status = 403, description = "Wrong admin token", body = ApiErrorBody),
+		(status = 404, description = "Unknown election", body = ApiErrorBody),
+	)
+)]
+async fn vote_count_events(
+	State(store): State<Store>,
+	Path((election_id, admin_id)): Path<(String, String)>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, FriendVoteError> {
+	let (summary, receiver) =
+		elections::subscribe_vote_counts(store.as_ref(), &election_id, &admin_id).await?;
+	Ok(vote_count_sse(store, election_id, summary, receiver))
+}
+
+#[utoipa::path(
+	get,
+	path = "/api/v1/elections/{election_id}/results/{result_id}/votes/events",
+	tag = "elections",
+	params(
+		("election_id" = String, Path, description = "Election id"),
+		("result_id" = String, Path, description = "Result token"),
+	),
+	responses(
+		(
+			status = 200,
+			description = "Server-sent events, each holding the vote count as JSON, like for admins. \
+				Results pages use them to know when to fetch the results again.",
+			content_type = "text/event-stream",
+			body = VoteCount,
+		),
+		(status = 403, description = "Wrong result token", body = ApiErrorBody),
+		(status = 404, description = "Unknown election", body = ApiErrorBody),
+	)
+)]
+async fn result_vote_count_events(
+	State(store): State<Store>,
+	Path((election_id, result_id)): Path<(String, String)>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, FriendVoteError> {
+	let (summary, receiver) =
+		elections::subscribe_result_vote_counts(store.as_ref(), &election_id, &result_id).await?;
+	Ok(vote_count_sse(store, election_id, summary, receiver))
+}
+
+/// Sends `summary`, then the vote count of the election after every new ballot.
+fn vote_count_sse(
+	store: Store,
+	election_id: String,
+	summary: VoteSummary,
+	receiver: broadcast::Receiver<VoteSummary>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+	let events = futures::stream::unfold(
+		(Some(summary), receiver, None),
+		move |(mut pending, mut receiver, mut last_n_votes)| {
+			let store = store.clone();
+			let election_id = election_id.clone();
+			async move {
+				loop {
+					let summary = match pending.take() {
+						Some(summary) => summary,
+						None => match receiver.recv().await {
+							Ok(summary) => summary,
+							// some votes were missed, so send the current count instead
+							Err(RecvError::Lagged(_)) => store.get_vote_summary(&election_id).await.ok()?,
+							Err(RecvError::Closed) => return None,
+						},
+					};
+					// skip other elections, and votes that were already counted when subscribing
+					if summary.election_id != election_id
+						|| last_n_votes.is_some_and(|last_n_votes| summary.n_votes <= last_n_votes)
+					{
+						continue;
+					}
+					last_n_votes = Some(summary.n_votes);
+					let event = Event::default().json_data(elections::vote_count(&summary));
+					return Some((event, (pending, receiver, last_n_votes)));
+				}
+			}
+		},
+	);
+	Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::time::Duration;
+
+	use axum::body::{to_bytes, Body};
+	use axum::http::Request;
+	use serde_json::{json, Value};
+	use tower::ServiceExt;
+
+	use super::*;
+	use crate::rate_limit::RateLimiter;
+	use crate::store::MemoryStore;
+
+	fn test_router() -> Router {
+		router(
+			Arc::new(MemoryStore::new()),
+			Arc::new(RateLimiter::new(10, Duration::from_secs(60))),
+			TrustedProxy::default(),
+		)
+	}
+
+	/// Sends a request with an optional JSON body, returning the status and the JSON response, if any.
+	async fn send(router: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+		let request = Request::builder().method(method).uri(uri);
+		let request = match body {
+			Some(body) => request
+				.header("content-type", "application/json")
+				.body(Body::from(body.to_string())),
+			None => request.body(Body::empty()),
+		}
+		.unwrap();
+		let response = router.clone().oneshot(request).await.unwrap();
+		let status = response.status();
+		let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+		let body = if bytes.is_empty() {
+			Value::Null
+		} else {
+			serde_json::from_slice(&bytes).unwrap()
+		};
+		(status, body)
+	}
+
+	async fn create(router: &Router) -> Value {
+		let (status, created) =
+			send(router, "POST", "/elections", Some(json!({"name": "Lunch", "candidates": ["A", "B", "C"]})))
+				.await;
+		assert_eq!(status, StatusCode::CREATED);
+		created
+	}
+
+	fn field<'a>(value: &'a Value, name: &str) -> &'a str {
+		value[name].as_str().unwrap()
+	}
+
+	#[tokio::test]
+	async fn elections_can_be_created_voted_in_and_counted() {
+		let router = test_router();
+		let created = create(&router).await;
+		let election_id = field(&created, "election_id");
+		assert_eq!(field(&created, "vote_path"), elections::vote_path(election_id));
+
+		let (status, info) = send(&router, "GET", &format!("/elections/{election_id}"), None).await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(info["candidates"], json!(["A", "B", "C"]));
+
+		let votes = format!("/elections/{election_id}/votes");
+		let (status, body) =
+			send(&router, "POST", &votes, Some(json!({"ranking": [["B"], ["A"], ["C"]]}))).await;
+		assert_eq!(status, StatusCode::CREATED);
+		assert_eq!(body, Value::Null);
+
+		let results = format!("/elections/{election_id}/results/{}", field(&created, "result_id"));
+		let (status, results) = send(&router, "GET", &results, None).await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(results["n_votes"], 1);
+		assert_eq!(results["ranked_candidates"], json!([["B"], ["A"], ["C"]]));
+	}
+
+	#[tokio::test]
+	async fn ballot_groups_become_ties_and_empty_groups_are_skipped() {
+		let router = test_router();
+		let created = create(&router).await;
+		let election_id = field(&created, "election_id");
+
+		let votes = format!("/elections/{election_id}/votes");
+		let ranking = json!({"ranking": [[], ["C"], [], ["A", "B"], []]});
+		let (status, _) = send(&router, "POST", &votes, Some(ranking)).await;
+		assert_eq!(status, StatusCode::CREATED);
+
+		let results = format!("/elections/{election_id}/results/{}", field(&created, "result_id"));
+		let (_, results) = send(&router, "GET", &results, None).await;
+		assert_eq!(results["ballot_frequencies"], json!([{"ranking": [["C"], ["A", "B"]], "count": 1}]));
+	}
+
+	#[tokio::test]
+	async fn errors_map_to_status_codes() {
+		let router = test_router();
+		let created = create(&router).await;
+		let election_id = field(&created, "election_id");
+		let admin = format!("/elections/{election_id}/admin/{}", field(&created, "admin_id"));
+		let votes = format!("/elections/{election_id}/votes");
+
+		let (status, body) = send(&router, "GET", "/elections/unknown", None).await;
+		assert_eq!(status, StatusCode::NOT_FOUND);
+		assert!(body["error"].is_string());
+
+		let (status, _) = send(&router, "GET", &format!("/elections/{election_id}/admin/wrong"), None).await;
+		assert_eq!(status, StatusCode::FORBIDDEN);
+		let (status, _) =
+			send(&router, "POST", &format!("/elections/{election_id}/admin/wrong/close"), None).await;
+		assert_eq!(status, StatusCode::FORBIDDEN);
+
+		let (status, _) =
+			send(&router, "POST", "/elections", Some(json!({"name": "", "candidates": []}))).await;
+		assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+		let (status, _) = send(&router, "POST", &votes, Some(json!({"ranking": [["A"], ["D"]]}))).await;
+		assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+		let (status, body) = send(&router, "POST", &format!("{admin}/close"), None).await;
+		assert_eq!(status, StatusCode::NO_CONTENT);
+		assert_eq!(body, Value::Null);
+		let (status, _) = send(&router, "POST", &votes, Some(json!({"ranking": [["A"], ["B"], ["C"]]}))).await;
+		assert_eq!(status, StatusCode::CONFLICT);
+
+		let (status, _) = send(&router, "POST", &format!("{admin}/reopen"), None).await;
+		assert_eq!(status, StatusCode::NO_CONTENT);
+		let (status, _) = send(&router, "POST", &votes, Some(json!({"ranking": [["A"], ["B"], ["C"]]}))).await;
+		assert_eq!(status, StatusCode::CREATED);
+	}
+}
@@ -8,19 +8,26 @@
 /// See the GNU General Public License for more details.
 /// You should have received a copy of the GNU General Public License along with FriendVote.
 /// If not, see <https://www.gnu.org/licenses/>.
-use leptos::{leptos_dom::logging::console_log, prelude::*};
-use leptos_meta::{provide_meta_context, MetaTags, Script, Stylesheet, Title};
+use leptos::prelude::*;
+use leptos_meta::{provide_meta_context, MetaTags, Stylesheet, Title};
 use leptos_router::{
 	components::{Outlet, ParentRoute, Route, Router, Routes},
-	hooks::use_params,
+	hooks::{use_params, use_query_map},
 	params::Params,
 	path,
 };
-#[cfg(feature = "hydrate")]
-use wasm_bindgen::prelude::wasm_bindgen;
 
-// use crate::components::{drag_list::DragList, ListItem};
-use crate::endpoints;
+use crate::components::{
+	draft::{clear_draft, restore_draft, save_draft},
+	drag_list::DragList,
+	my_elections::{remember_election, MyElection, MyElections},
+	pairwise_questions::PairwiseQuestions,
+	passkeys::{AddPasskey, PasskeyLogin},
+	rank_select::{order_from_ranks, ranks_from_query, RankSelectList},
+	ranking_in_words, ListItem,
+};
+use crate::endpoints::{self, MemberVoterStatus};
+use crate::errors::FriendVoteError;
 
 pub fn shell(options: LeptosOptions) -> impl IntoView {
 	view! {
@@ -56,13 +63,17 @@ pub fn App() -> impl IntoView {
 		// content for this welcome page
 		<Router>
 			<main>
-				<Routes fallback=|| "Page not found.".into_view()>
+				<Routes fallback=|| view! { <NotFoundPage/> }>
 					<Route path=path!("") view=HomePage/>
 					<Route path=path!("vote_thanks") view=VoteThanksPage/>
+					<Route path=path!("account") view=AccountPage/>
 					<ParentRoute path=path!(":election_id") view=VoteParent>
+						// the owner of an election can leave out the admin token, when logged in
+						<Route path=path!("admin") view=AdminPage/>
 						<Route path=path!("admin/:admin_id") view=AdminPage/>
 						<Route path=path!("vote") view=VotePage/>
 						<Route path=path!("result/:result_id") view=ResultPage/>
+						<Route path=path!("present/:result_id") view=PresentationPage/>
 					</ParentRoute>
 				</Routes>
 			</main>
@@ -73,6 +84,7 @@ pub fn App() -> impl IntoView {
 #[component]
 fn HomePage() -> impl IntoView {
 	let create_election = ServerAction::<endpoints::CreateElection>::new();
+	let oidc_provider = OnceResource::new(endpoints::get_oidc_provider_name());
 	let candidates = RwSignal::new(Vec::<(RwSignal<bool>, RwSignal<String>)>::new());
 	candidates
 		.write_untracked()
@@ -83,6 +95,7 @@ fn HomePage() -> impl IntoView {
 
 	view! {
 		<h1>"Welcome to FriendVote!"</h1>
+		<JoinForm/>
 		<div style="display:flex;justify-content:center">
 			<div>
 				<ActionForm action=create_election>
@@ -117,14 +130,64 @@ fn HomePage() -> impl IntoView {
 							}
 						}
 					/>
+					<div style="margin:5px">
+						<input id="shuffle_candidates" type="checkbox" name="shuffle_candidates" value="true"/>
+						<label for="shuffle_candidates">"Show the candidates in a random order to each voter"</label>
+					</div>
+					<div style="margin:5px">
+						<input id="review_ballots" type="checkbox" name="review_ballots" value="true"/>
+						<label for="review_ballots">"Ask voters to review their ranking before submitting it"</label>
+					</div>
+					// members-only elections need a provider to log in through
+					<Suspense>
+						{move || Suspend::new(async move {
+							oidc_provider.await.ok().flatten().map(|provider_name| view! {
+								<div style="margin:5px">
+									<input id="members_only" type="checkbox" name="members_only" value="true"/>
+									<label for="members_only">
+										{format!("Only let members who log in with {} vote, once each", provider_name)}
+									</label>
+								</div>
+							})
+						})}
+					</Suspense>
 					<input type="submit" on:click:target=move |event| {let _ = event.target().form().expect("form to be connected").request_submit();event.target().set_disabled(true); event.target().set_value("Submitting…");}/>
 				</ActionForm>
+				<MyElections/>
+				<p><a href="/account">"Your account"</a></p>
 				<ExtraInfo/>
 			</div>
 		</div>
 	}
 }
 
+/// Takes voters with a join code to the vote page.
+#[component]
+fn JoinForm() -> impl IntoView {
+	let join_election = ServerAction::<endpoints::JoinElection>::new();
+
+	view! {
+		<div class="join-form">
+			<ActionForm action=join_election>
+				<label for="join_code">"Have a join code? "</label>
+				<input
+					id="join_code"
+					type="text"
+					name="join_code"
+					maxlength="7"
+					autocomplete="off"
+					autocapitalize="characters"
+					spellcheck="false"
+				/>
+				<input type="submit" value="Join"/>
+			</ActionForm>
+			{move || join_election.value().get().and_then(Result::err).map(|error| view! {
+				<p class="error">{FriendVoteError::from_server_fn_error(&error).message().to_string()}</p>
+			})}
+		</div>
+	}
+}
+
 #[component]
 fn VoteParent() -> impl IntoView {
 	view! {
@@ -159,41 +222,58 @@ fn AdminPage() -> impl IntoView {
 			.unwrap_or_default()
 	};
 	let election_info = OnceResource::new(endpoints::get_election_admin_info(election_id(), admin_id()));
-	let location = move || window().location().origin().expect("no location found on window");
+	let election_links = OnceResource::new(endpoints::get_election_links(election_id(), admin_id()));
 
 	view! {
 		<Suspense
 			fallback=move || view! { <p>"Fetching election details..."</p> }
 		>
-			<h1>"Admin page for '" {Suspend::new(async move {election_info.await.unwrap().election_name})} "'"</h1>
-			<div style="text-align: center">
-				<div style="display: inline-block;text-align: left">
-					<p>
-						"admin url: "
-						<a
-							href={Suspend::new(async move {location() + "/" + &election_info.await.unwrap().admin_path})}
-						>
-						{location()} "/" {Suspend::new(async move {election_info.await.unwrap().admin_path})}
-						</a>
-					</p>
-					<p>
-						"vote url: "
-						<a
-							href={Suspend::new(async move {location() + "/" + &election_info.await.unwrap().vote_path})}
-						>
-						{location()} "/" {Suspend::new(async move {election_info.await.unwrap().vote_path})}
-						</a>
-					</p>
-					<p>
-						"results url: "
-						<a
-							href={Suspend::new(async move {location() + "/" + &election_info.await.unwrap().result_path})}
-						>
-						{location()} "/" {Suspend::new(async move {election_info.await.unwrap().result_path})}
-						</a>
-					</p>
-				</div>
-			</div>
+			<ErrorBoundary fallback=|errors| view! { <ElectionErrors errors/> }>
+				{
+					Suspend::new(async move {
+						let links = election_links.await?;
+						election_info.await.map(|info| {
+							// owners may have opened the page without the token, but the live vote count needs it
+							let admin_id = info.admin_id.clone();
+							// the admin link is the only way back to this page, so the browser keeps it for the home page
+							let election = MyElection {
+								election_id: election_id(),
+								admin_id: admin_id.clone(),
+								name: info.election_name.clone(),
+							};
+							Effect::new(move |_| remember_election(election.clone()));
+							let voting_open = RwSignal::new(info.voting_open);
+							let join_code = RwSignal::new(info.join_code);
+							view! {
+								<h1>"Admin page for '" {info.election_name.clone()} "'"</h1>
+								<div style="text-align: center">
+									<div style="display: inline-block;text-align: left">
+										<ElectionLink label="admin url: " link=links.admin/>
+										<ElectionLink label="vote url: " link=links.vote.clone()/>
+										<ElectionLink label="results url: " link=links.result/>
+									</div>
+								</div>
+								<JoinCard election_name=info.election_name link=links.vote join_code/>
+								<VoteCounter election_id=election_id() admin_id=admin_id.clone() vote_count=info.vote_count/>
+								<VotingOpenToggle election_id=election_id() admin_id=admin_id.clone() voting_open/>
+								<JoinCodeControl election_id=election_id() admin_id=admin_id.clone() voting_open join_code/>
+								<div style="display: flex;justify-content: center">
+									<details class="audit-log">
+										<summary style="cursor:pointer">"History"</summary>
+										<ul>
+											{
+												info.audit_log.into_iter().map(|entry| view! {
+													<li>{entry.time} ": " {entry.action}</li>
+												}).collect_view()
+											}
+										</ul>
+									</details>
+								</div>
+							}
+						})
+					})
+				}
+			</ErrorBoundary>
 		</Suspense>
 		<div>
 			<ExtraInfo/>
@@ -201,30 +281,194 @@ fn AdminPage() -> impl IntoView {
 	}
 }
 
-#[derive(Params, PartialEq)]
-struct VotePageParams {
-	election_id: Option<String>,
+/// A link with its QR code, folded away so that secret links are not shown to a whole room by accident.
+#[component]
+fn ElectionLink(label: &'static str, link: endpoints::QrLink) -> impl IntoView {
+	view! {
+		<div class="election-link">
+			{label}
+			<a href=link.url.clone()>{link.url.clone()}</a>
+			<details>
+				<summary>"QR code"</summary>
+				<div class="qr-code" inner_html=link.qr_code></div>
+			</details>
+		</div>
+	}
+}
+
+/// The vote link, large enough to scan from across a room. It is the only part of the page that gets printed.
+#[component]
+fn JoinCard(
+	election_name: String,
+	link: endpoints::QrLink,
+	join_code: RwSignal<Option<String>>,
+) -> impl IntoView {
+	view! {
+		<section class="join-card">
+			<h2>"Join this vote"</h2>
+			<p class="join-card-election">{election_name}</p>
+			<div class="qr-code" inner_html=link.qr_code></div>
+			<p>"Scan the code, or go to"</p>
+			<p class="join-card-url">{link.url}</p>
+			{move || join_code.get().map(|join_code| view! {
+				<p>"or enter the join code " <strong class="join-code">{join_code}</strong> " on the FriendVote home page"</p>
+			})}
+		</section>
+		<button
+			on:click=move |_| {
+				let _ = window().print();
+			}
+		>
+			"Print join card"
+		</button>
+	}
+}
+
+/// Shows the number of ballots, kept up to date through server-sent events.
+#[component]
+fn VoteCounter(election_id: String, admin_id: String, vote_count: endpoints::VoteCount) -> impl IntoView {
+	let vote_count = RwSignal::new(vote_count);
+	#[cfg(feature = "hydrate")]
+	follow_vote_count(
+		&format!("/api/v1/elections/{}/admin/{}/votes/events", election_id, admin_id),
+		move |new_count| vote_count.set(new_count),
+	);
+	#[cfg(not(feature = "hydrate"))]
+	let _ = (election_id, admin_id);
+
+	view! {
+		<p style="margin-top: 20px">
+			{move || {
+				let vote_count = vote_count.read();
+				let n_votes = match vote_count.n_votes {
+					0 => return "No votes yet.".to_string(),
+					1 => "1 vote".to_string(),
+					n_votes => format!("{} votes", n_votes),
+				};
+				match &vote_count.last_vote_at {
+					Some(last_vote_at) => format!("{}, the latest at {}.", n_votes, last_vote_at),
+					None => format!("{}.", n_votes),
+				}
+			}}
+		</p>
+	}
 }
 
+/// Passes every vote count received from the server-sent events at `url` to `on_vote_count`,
+/// until the calling component is cleaned up.
 #[cfg(feature = "hydrate")]
-// #[wasm_bindgen(module = "/Sortable.js")]
-#[wasm_bindgen()]
-extern "C" {
-	type Sortable;
+fn follow_vote_count(url: &str, mut on_vote_count: impl FnMut(endpoints::VoteCount) + 'static) {
+	use leptos::leptos_dom::logging::console_log;
+	use wasm_bindgen::{closure::Closure, JsCast};
 
-	#[wasm_bindgen(constructor)]
-	fn new(element_id: String) -> Sortable;
+	match web_sys::EventSource::new(url) {
+		Ok(event_source) => {
+			let on_message =
+				Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |event: web_sys::MessageEvent| {
+					if let Some(vote_count) = event
+						.data()
+						.as_string()
+						.and_then(|data| serde_json::from_str(&data).ok())
+					{
+						on_vote_count(vote_count);
+					}
+				});
+			event_source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+			// the closure must live as long as the event source
+			let subscription = send_wrapper::SendWrapper::new((event_source, on_message));
+			on_cleanup(move || subscription.0.close());
+		},
+		Err(_) => console_log("could not subscribe to the vote count"),
+	}
 }
-#[cfg(feature = "ssr")]
-struct Sortable;
-#[cfg(feature = "ssr")]
-impl Sortable {
-	fn new(element_id: String) -> Sortable {
-		let _ = element_id;
-		Sortable {}
+
+#[component]
+fn VotingOpenToggle(election_id: String, admin_id: String, voting_open: RwSignal<bool>) -> impl IntoView {
+	let set_voting_open = ServerAction::<endpoints::SetVotingOpen>::new();
+	let requested_open = RwSignal::new(voting_open.get_untracked());
+	Effect::new(move |_| {
+		if let Some(Ok(())) = set_voting_open.value().get() {
+			voting_open.set(requested_open.get_untracked());
+		}
+	});
+
+	view! {
+		<div style="margin-top: 20px">
+			<p>{move || if voting_open.get() {"Voting is open."} else {"Voting is closed."}}</p>
+			<button
+				disabled=move || set_voting_open.pending().get()
+				on:click=move |_| {
+					requested_open.set(!voting_open.get_untracked());
+					set_voting_open.dispatch(endpoints::SetVotingOpen {
+						election_id: election_id.clone(),
+						admin_id: admin_id.clone(),
+						voting_open: requested_open.get_untracked(),
+					});
+				}
+			>
+				{move || if voting_open.get() {"Close voting"} else {"Reopen voting"}}
+			</button>
+		</div>
+	}
+}
+
+/// Shows the join code, or lets the admin create one while voting is open.
+#[component]
+fn JoinCodeControl(
+	election_id: String,
+	admin_id: String,
+	voting_open: RwSignal<bool>,
+	join_code: RwSignal<Option<String>>,
+) -> impl IntoView {
+	let create_join_code = ServerAction::<endpoints::CreateJoinCode>::new();
+	Effect::new(move |_| {
+		if let Some(Ok(new_join_code)) = create_join_code.value().get() {
+			join_code.set(Some(new_join_code));
+		}
+	});
+	// closing voting frees the code on the server
+	Effect::new(move |_| {
+		if !voting_open.get() {
+			join_code.set(None);
+		}
+	});
+
+	view! {
+		<div style="margin-top: 20px">
+			{move || match join_code.get() {
+				Some(join_code) => view! {
+					<p>"Join code: " <strong class="join-code">{join_code}</strong></p>
+				}.into_any(),
+				None => {
+					let election_id = election_id.clone();
+					let admin_id = admin_id.clone();
+					view! {
+						<button
+							disabled=move || !voting_open.get() || create_join_code.pending().get()
+							on:click=move |_| {
+								create_join_code.dispatch(endpoints::CreateJoinCode {
+									election_id: election_id.clone(),
+									admin_id: admin_id.clone(),
+								});
+							}
+						>
+							"Create join code"
+						</button>
+					}.into_any()
+				},
+			}}
+			{move || create_join_code.value().get().and_then(Result::err).map(|error| view! {
+				<p class="error">{FriendVoteError::from_server_fn_error(&error).message().to_string()}</p>
+			})}
+		</div>
 	}
 }
 
+#[derive(Params, PartialEq)]
+struct VotePageParams {
+	election_id: Option<String>,
+}
+
 #[component]
 fn VotePage() -> impl IntoView {
 	let params = use_params::<VotePageParams>();
@@ -238,74 +482,194 @@ fn VotePage() -> impl IntoView {
 			.unwrap_or_default()
 	};
 	let election_info = OnceResource::new(endpoints::get_election_vote_info(election_id()));
+	let voter_status = OnceResource::new(endpoints::get_member_voter_status(election_id()));
+	let query = use_query_map();
 
-	let list_loaded = RwSignal::new(false);
-	let list_sortable = RwSignal::new(false);
-	Effect::new(move |_| {
-		if list_loaded.get() && !list_sortable.get() {
-			console_log("applying Sortable");
-			Sortable::new("example1".to_string());
-			list_sortable.set(true);
-		}
-	});
 	let cast_vote = ServerAction::<endpoints::CastVote>::new();
 
 	view! {
-		<Script src="/Sortable.js"></Script>
 		<Suspense
 			fallback=move || view! { <p>"Fetching election details..."</p> }
 		>
-			<h1>"Voting page for '"{Suspend::new(async move {election_info.await.unwrap().election_name})}"'"</h1>
-			<div style="display: inline-block">
+			<ErrorBoundary fallback=|errors| view! { <ElectionErrors errors/> }>
 				{
-					move || {
-						election_info.get().map(
-							|info| view! {
-
+					Suspend::new(async move {
+						let info = election_info.await?;
+						if !info.voting_open {
+							return Err(ServerFnError::WrappedServerError(FriendVoteError::Closed(
+								"The administrator has closed voting for this election.".to_string(),
+							)));
+						}
+						if info.members_only {
+							match voter_status.await? {
+								MemberVoterStatus::LoggedOut => {
+									return Ok(view! {
+										<h1>"Voting page for '"{info.election_name}"'"</h1>
+										<p>"Only members can vote in this election."</p>
+										<p><a href=oidc_login_href(&format!("/{}/vote", election_id())) rel="external">"Log in to vote"</a></p>
+									}
+									.into_any());
+								},
+								MemberVoterStatus::NotMember => {
+									return Err(ServerFnError::WrappedServerError(FriendVoteError::CannotVote(
+										"Only members of the voter group can vote in this election.".to_string(),
+									)));
+								},
+								MemberVoterStatus::Voted => {
+									return Err(ServerFnError::WrappedServerError(FriendVoteError::CannotVote(
+										"You already voted in this election.".to_string(),
+									)));
+								},
+								MemberVoterStatus::CanVote => {},
+							}
+						}
+						// without JavaScript, the ranking is reviewed on a page of its own, which gets it in the query
+						if let Some((candidates, ranks)) = query.with_untracked(ranks_from_query).filter(|_| info.review_ballots) {
+							let (ordered, comparator_is_bigger) =
+								order_from_ranks(candidates.clone(), &ranks).map_err(ServerFnError::WrappedServerError)?;
+							let items = ordered
+								.into_iter()
+								.zip(comparator_is_bigger.into_iter().map(|is_bigger| !is_bigger).chain([false]))
+								.enumerate()
+								.map(|(id, (label, tied_with_next))| ListItem { id, label, tied_with_next })
+								.collect::<Vec<_>>();
+							return Ok(view! {
+								<h1>"Voting page for '"{info.election_name}"'"</h1>
+								<div style="display: inline-block">
+									<ActionForm action=cast_vote>
+										<input type="hidden" id="election_id" name="election_id" value=election_id/>
+										<div class="ballot-review">
+											<p>"Your ranking:"</p>
+											<p class="ballot-review-ranking">{ranking_in_words(&items)}</p>
+											<a href=format!("/{}/vote", election_id())>"Change my ranking"</a>
+										</div>
+										{candidates.into_iter().zip(ranks).enumerate().map(|(index, (candidate, rank))| view! {
+											<input type="hidden" name=format!("candidates[{}]", index) value=candidate/>
+											<input type="hidden" name=format!("ranks[{}]", index) value=rank/>
+										}).collect_view()}
+										<input type="submit" style="margin-top: 20px" value="Confirm my vote"/>
+									</ActionForm>
+									{
+										move || cast_vote.value().get().and_then(|result| result.err()).map(|error| view! {
+											<p class="error">{FriendVoteError::from_server_fn_error(&error).message().to_string()}</p>
+										})
+									}
+								</div>
+							}
+							.into_any());
+						}
+						let items = RwSignal::new(
+							info.candidates
+								.into_iter()
+								.enumerate()
+								.map(|(id, label)| ListItem { id, label, tied_with_next: false })
+								.collect::<Vec<_>>(),
+						);
+						// the server renders a ballot that needs no JavaScript, replaced by the drag list once it runs
+						let hydrated = RwSignal::new(false);
+						// the ranking in progress is kept in the browser until it is submitted, so that it survives reloads
+						let draft_id = StoredValue::new(election_id());
+						Effect::new(move |_| {
+							draft_id.with_value(|draft_id| items.update(|items| restore_draft(draft_id, items)));
+							hydrated.set(true);
+						});
+						Effect::new(move |_| {
+							if hydrated.get() {
+								draft_id.with_value(|draft_id| items.with(|items| save_draft(draft_id, items)));
+							}
+						});
+						// the draft is only cleared once the vote is stored, which is before the redirect leaves the page
+						Effect::new(move |_| {
+							if cast_vote.value().with(|result| matches!(result, Some(Ok(_)))) {
+								draft_id.with_value(|draft_id| clear_draft(draft_id));
+							}
+						});
+						// with a review step, the ranking is shown in words before the voter can submit it
+						let review_ballots = info.review_ballots;
+						let reviewing = RwSignal::new(false);
+						let needs_review = move || review_ballots && hydrated.get() && !reviewing.get();
+						// long lists can be ranked by answering which of two candidates is preferred, over and over
+						let asking = RwSignal::new(false);
+						let ballot = move || {
+							if !hydrated.get() {
+								let candidates = items.with_untracked(|items| items.iter().map(|item| item.label.clone()).collect());
+								return view! {
+									<RankSelectList candidates/>
+									// the review page is the vote page itself, with the ranking in its query
+									{review_ballots.then(|| view! {
+										<button
+											type="submit"
+											style="margin-top: 20px"
+											formmethod="get"
+											formaction=format!("/{}/vote", election_id())
+										>
+											"Review my vote"
+										</button>
+									})}
+								}
+								.into_any();
+							}
+							view! {
+								<Show when=move || !asking.get() && !reviewing.get()>
+									<button type="button" class="ballot-mode-switch" on:click=move |_| asking.set(true)>
+										"Rank by answering questions instead"
+									</button>
+								</Show>
+								<Show when=move || asking.get()>
+									<PairwiseQuestions items asking/>
+								</Show>
+								<div hidden=move || reviewing.get() || asking.get()>
+									<DragList items/>
+								</div>
+								<Show when=move || reviewing.get()>
+									<div class="ballot-review">
+										<p>"Your ranking:"</p>
+										<p class="ballot-review-ranking">{move || items.with(|items| ranking_in_words(items))}</p>
+										<button type="button" on:click=move |_| reviewing.set(false)>"Change my ranking"</button>
+									</div>
+								</Show>
+								// the ranking as the server expects it: the candidates in order, and for each one
+								// that is preferred over the next, its position as `comp_<position>`
+								{move || items.get().into_iter().enumerate().map(|(position, item)| view! {
+									<input type="hidden" name=format!("candidates[{}]", position) value=item.label/>
+									{(!item.tied_with_next).then(|| view! {
+										<input type="hidden" name=format!("comp[{}]", position) value=format!("comp_{}", position)/>
+									})}
+								}).collect_view()}
+							}
+							.into_any()
+						};
+						Ok(view! {
+							<h1>"Voting page for '"{info.election_name}"'"</h1>
+							<div style="display: inline-block">
 								<ActionForm action=cast_vote>
 									<input type="hidden" id="election_id" name="election_id" value=election_id/>
-									<div
-										id="example1"
-										class="list-group col"
-										style="width:fit-content"
-										on:mouseover=move |_| {list_loaded.set(true)}
-										on:touchstart=move |_| {list_loaded.set(true)}
-									>
-										{
-											info.expect("failed to fetch candidates").candidates.iter().enumerate().map(|(ind, cand)| view!{
-												<div class="list-group-item">
-													<input
-														type="text"
-														class="inert-text-input"
-														name=move || format!("candidates[{}]", ind)
-														prop:value={cand.clone()}
-														readonly
-													/>
-													<div class="checkbox-wrapper">
-														<input
-															id=move || format!("comp_{}", ind)
-															class="comp-checkbox"
-															type="checkbox"
-															name=move || format!("comp[{}]", ind)
-															checked
-															prop:value=move || format!("comp_{}", ind)
-														/>
-														<label for=move || format!("comp_{}", ind)></label>
-													</div>
-												</div>
-											}).collect_view()
-										}
-									</div>
+									{ballot}
+									<Show when=move || needs_review() && !asking.get()>
+										<button type="button" style="margin-top: 20px" on:click=move |_| reviewing.set(true)>
+											"Review my vote"
+										</button>
+									</Show>
 									<input
 										type="submit"
 										style="margin-top: 20px"
+										hidden=move || needs_review() || asking.get() || (review_ballots && !hydrated.get())
+										value=move || if reviewing.get() { "Confirm my vote" } else { "Submit" }
 										on:click:target=move |event| {let _ = event.target().form().expect("form to be connected").request_submit();event.target().set_disabled(true); event.target().set_value("Submitting…");}
 									/>
 								</ActionForm>
-							}
-						)
-					}
+								{
+									move || cast_vote.value().get().and_then(|result| result.err()).map(|error| view! {
+										<p class="error">{FriendVoteError::from_server_fn_error(&error).message().to_string()}</p>
+									})
+								}
+							</div>
+						}
+						.into_any())
+					})
 				}
+			</ErrorBoundary>
+			<div style="display: inline-block">
 				<div>
 					<ExtraInfo/>
 				</div>
@@ -314,6 +678,11 @@ fn VotePage() -> impl IntoView {
 	}
 }
 
+/// The login at the OpenID Connect provider, which comes back to `return_to`. It is served outside of the router.
+fn oidc_login_href(return_to: &str) -> String {
+	format!("/auth/oidc/login?return_to={}", return_to)
+}
+
 #[component]
 fn VoteThanksPage() -> impl IntoView {
 	view! {
@@ -324,6 +693,105 @@ fn VoteThanksPage() -> impl IntoView {
 	}
 }
 
+/// Optional organizer accounts, which keep the elections created while logged in in one place,
+/// where they can be managed without their admin links.
+#[component]
+fn AccountPage() -> impl IntoView {
+	let sign_up = ServerAction::<endpoints::SignUp>::new();
+	let log_in = ServerAction::<endpoints::LogIn>::new();
+	let log_out = ServerAction::<endpoints::LogOut>::new();
+	let passkey_logins = RwSignal::new(0);
+	let oidc_provider = OnceResource::new(endpoints::get_oidc_provider_name());
+	let account = Resource::new(
+		move || {
+			(sign_up.version().get(), log_in.version().get(), log_out.version().get(), passkey_logins.get())
+		},
+		|_| endpoints::get_account_info(),
+	);
+
+	view! {
+		<h1>"Your account"</h1>
+		<Suspense fallback=move || view! { <p>"Fetching your account..."</p> }>
+			<ErrorBoundary fallback=|errors| view! { <ElectionErrors errors/> }>
+				{move || Suspend::new(async move {
+					let oidc_provider = oidc_provider.await.ok().flatten();
+					account.await.map(|account| match account {
+						Some(account) => view! {
+							<div class="account">
+								<p>"Logged in as " <strong>{account.username}</strong></p>
+								<ActionForm action=log_out>
+									<input type="submit" value="Log out"/>
+								</ActionForm>
+								<AddPasskey n_passkeys=account.n_passkeys/>
+								<section class="account-elections">
+									<h2>"Your elections"</h2>
+									{if account.elections.is_empty() {
+										view! { <p>"Elections you create while logged in are listed here."</p> }.into_any()
+									} else {
+										view! {
+											<ul>
+												{account.elections.into_iter().map(|election| view! {
+													<li><a href=format!("/{}", election.admin_path)>{election.name}</a></li>
+												}).collect_view()}
+											</ul>
+										}.into_any()
+									}}
+								</section>
+							</div>
+						}.into_any(),
+						None => view! {
+							<div class="account">
+								<p>"An account is optional. Elections you create while logged in are kept in it."</p>
+								{oidc_provider.map(|provider_name| view! {
+									<p class="oidc-login">
+										<a href=oidc_login_href("/account") rel="external">{format!("Log in with {}", provider_name)}</a>
+									</p>
+								})}
+								<section>
+									<h2>"Log in"</h2>
+									<ActionForm action=log_in>
+										<div>
+											<label for="log_in_username">"Username: "</label>
+											<input id="log_in_username" type="text" name="username" autocomplete="username"/>
+										</div>
+										<div>
+											<label for="log_in_password">"Password: "</label>
+											<input id="log_in_password" type="password" name="password" autocomplete="current-password"/>
+										</div>
+										<input type="submit" value="Log in"/>
+									</ActionForm>
+									{move || log_in.value().get().and_then(Result::err).map(|error| view! {
+										<p class="error">{FriendVoteError::from_server_fn_error(&error).message().to_string()}</p>
+									})}
+								</section>
+								<PasskeyLogin logins=passkey_logins/>
+								<section>
+									<h2>"Sign up"</h2>
+									<ActionForm action=sign_up>
+										<div>
+											<label for="sign_up_username">"Username: "</label>
+											<input id="sign_up_username" type="text" name="username" autocomplete="username"/>
+										</div>
+										<div>
+											<label for="sign_up_password">"Password: "</label>
+											<input id="sign_up_password" type="password" name="password" autocomplete="new-password"/>
+										</div>
+										<input type="submit" value="Sign up"/>
+									</ActionForm>
+									{move || sign_up.value().get().and_then(Result::err).map(|error| view! {
+										<p class="error">{FriendVoteError::from_server_fn_error(&error).message().to_string()}</p>
+									})}
+								</section>
+							</div>
+						}.into_any(),
+					})
+				})}
+			</ErrorBoundary>
+		</Suspense>
+		<p style="text-align: center"><a href="/">"Go to the home page"</a></p>
+	}
+}
+
 #[derive(Params, PartialEq)]
 struct ResultPageParams {
 	election_id: Option<String>,
@@ -350,97 +818,400 @@ fn ResultPage() -> impl IntoView {
 			.and_then(|params| params.result_id.clone())
 			.unwrap_or_default()
 	};
-	let election_results = OnceResource::new(endpoints::get_election_results(election_id(), result_id()));
+	// the latest vote count pushed by the server, so the results are fetched again when ballots come in
+	let n_votes = RwSignal::new(None::<u64>);
+	#[cfg(feature = "hydrate")]
+	follow_vote_count(
+		&format!("/api/v1/elections/{}/results/{}/votes/events", election_id(), result_id()),
+		move |vote_count| {
+			if n_votes.get_untracked() != Some(vote_count.n_votes) {
+				n_votes.set(Some(vote_count.n_votes));
+			}
+		},
+	);
+	let election_results = Resource::new(
+		move || n_votes.get(),
+		move |_| endpoints::get_election_results(election_id(), result_id()),
+	);
 
 	view! {
-		<Suspense
+		// unlike Suspense, Transition keeps showing the previous results while new ones are fetched
+		<Transition
 			fallback=move || view! { <p>"Fetching election results..."</p> }
 		>
-			{
-				Suspend::new(
-					async move {
-						let results = election_results.await.unwrap();
-						let ranked_candidates = results.ranked_candidates.clone();
-						view! {
-							<h1>"Results page for '" {results.election_name} "'"</h1>
-							<div style="text-align: center">
-								<h4>"Number of votes: " {results.n_votes}</h4>
-								<h4>"Defeats matrix:"</h4>
-								<table class="defeat-matrix">
-									<thead>
-										<tr>
-											<th scope="col"></th>
+			<ErrorBoundary fallback=|errors| view! { <ElectionErrors errors/> }>
+				{
+					Suspend::new(
+						async move {
+							let results = election_results.await?;
+							let ranked_candidates = results.ranked_candidates.clone();
+							Ok::<_, ServerFnError<FriendVoteError>>(view! {
+								<h1>"Results page for '" {results.election_name} "'"</h1>
+								<p><a href=format!("/{}/present/{}", election_id(), result_id())>"Present on a big screen"</a></p>
+								<div style="text-align: center">
+									<h4>"Number of votes: " {results.n_votes}</h4>
+									<h4>"Defeats matrix:"</h4>
+									<table class="defeat-matrix">
+										<thead>
+											<tr>
+												<th scope="col"></th>
+												{
+													results.candidates.iter().map(
+														|cand| view!{
+															<th scope="col"><span>{cand.clone()}</span></th>
+														}
+													).collect_view()
+												}
+											</tr>
+										</thead>
+										<tbody>
 											{
-												results.candidates.iter().map(
-													|cand| view!{
-														<th scope="col"><span>{cand.clone()}</span></th>
+												results.defeats_matrix.iter().enumerate().map(
+													|(row_ind, defeats_row)| view!{
+														<tr>
+															<th scope="row">{results.candidates[row_ind].clone()}</th>
+															{
+																defeats_row.iter().map(
+																	|defeat| view! {
+																		<td>{*defeat}</td>
+																	}
+																).collect_view()
+															}
+														</tr>
 													}
 												).collect_view()
 											}
-										</tr>
-									</thead>
-									<tbody>
-										{
-											results.defeats_matrix.iter().enumerate().map(
-												|(row_ind, defeats_row)| view!{
-													<tr>
-														<th scope="row">{results.candidates[row_ind].clone()}</th>
-														{
-															defeats_row.iter().map(
-																|defeat| view! {
-																	<td>{*defeat}</td>
-																}
-															).collect_view()
+										</tbody>
+									</table>
+									<h4>"Global ranks:"</h4>
+									<table class="ranks">
+										<thead>
+											<tr>
+												<th scope="col">"rank"</th>
+												<th scope="col">"candidate(s)"</th>
+											</tr>
+										</thead>
+										<tbody>
+											{
+												move || {
+													let ranked_candidates = results.ranked_candidates.clone();
+													ranked_candidates.iter().enumerate().map(
+														|(rank, candidates)| view!{
+															<tr>
+																<th scope="row">{rank}</th>
+																<td>{candidates.join(" / ")}</td>
+															</tr>
 														}
-													</tr>
+													).collect_view()
+												}
+											}
+										</tbody>
+									</table>
+									{
+										move || {
+											if ranked_candidates[0].len() > 1 {
+												view! {
+													<h4>"No winner, tied between: " {ranked_candidates[0].join(", ")}</h4>
+												}
+											} else {
+												view! {
+													<h4>"Winner: " {ranked_candidates[0][0].clone()}</h4>
 												}
-											).collect_view()
+											}
 										}
-									</tbody>
-								</table>
-								<h4>"Global ranks:"</h4>
-								<table class="ranks">
-									<thead>
-										<tr>
-											<th scope="col">"rank"</th>
-											<th scope="col">"candidate(s)"</th>
-										</tr>
-									</thead>
-									<tbody>
-										{
-											move || {
-												let ranked_candidates = results.ranked_candidates.clone();
-												ranked_candidates.iter().enumerate().map(
-													|(rank, candidates)| view!{
+									}
+									<h4>"Ballots:"</h4>
+									<table class="ballot-frequencies">
+										<thead>
+											<tr>
+												<th scope="col">"voters"</th>
+												<th scope="col">"ranking"</th>
+											</tr>
+										</thead>
+										<tbody>
+											{
+												results.ballot_frequencies.iter().map(
+													|frequency| view!{
 														<tr>
-															<th scope="row">{rank}</th>
-															<td>{candidates.join(" / ")}</td>
+															<td>{frequency.count}</td>
+															<td>
+																{
+																	frequency.ranking.iter().map(|group| group.join(" = "))
+																		.collect::<Vec<_>>().join(" > ")
+																}
+															</td>
 														</tr>
 													}
 												).collect_view()
 											}
-										}
-									</tbody>
-								</table>
-								{
-									move || {
-										if ranked_candidates[0].len() > 1 {
-											view! {
-												<h4>"No winner, tied between: " {ranked_candidates[0].join(", ")}</h4>
-											}
-										} else {
-											view! {
-												<h4>"Winner: " {ranked_candidates[0][0].clone()}</h4>
-											}
-										}
-									}
-								}
-							</div>
+										</tbody>
+									</table>
+								</div>
+							})
 						}
-					}
-				)
+					)
+				}
+			</ErrorBoundary>
+		</Transition>
+	}
+}
+
+/// Results for a projector: the ranking is revealed one rank at a time, from last place to first.
+#[component]
+fn PresentationPage() -> impl IntoView {
+	let params = use_params::<ResultPageParams>();
+
+	let election_id = move || {
+		params
+			.read_untracked()
+			.as_ref()
+			.ok()
+			.and_then(|params| params.election_id.clone())
+			.unwrap_or_default()
+	};
+	let result_id = move || {
+		params
+			.read_untracked()
+			.as_ref()
+			.ok()
+			.and_then(|params| params.result_id.clone())
+			.unwrap_or_default()
+	};
+	// fetched again on every new vote, like the results page
+	let n_votes = RwSignal::new(None::<u64>);
+	#[cfg(feature = "hydrate")]
+	follow_vote_count(
+		&format!("/api/v1/elections/{}/results/{}/votes/events", election_id(), result_id()),
+		move |vote_count| {
+			if n_votes.get_untracked() != Some(vote_count.n_votes) {
+				n_votes.set(Some(vote_count.n_votes));
 			}
-		</Suspense>
+		},
+	);
+	let presentation =
+		Resource::new(move || n_votes.get(), move |_| endpoints::get_presentation(election_id(), result_id()));
+
+	// the number of ranks shown, counted from the last one
+	let n_revealed = RwSignal::new(0usize);
+	let n_ranks = move || {
+		presentation
+			.get()
+			.and_then(Result::ok)
+			.map(|presentation| presentation.results.ranked_candidates.len())
+			.unwrap_or(0)
+	};
+	let all_revealed = move || n_ranks() <= n_revealed.get();
+	let reveal_next = move || {
+		if !all_revealed() {
+			n_revealed.update(|n_revealed| *n_revealed += 1);
+		}
+	};
+	let hide_last = move || n_revealed.update(|n_revealed| *n_revealed = n_revealed.saturating_sub(1));
+	#[cfg(feature = "hydrate")]
+	{
+		// presentation remotes send page down and page up
+		let handle = window_event_listener(leptos::ev::keydown, move |event| match event.key().as_str() {
+			"ArrowRight" | "PageDown" => reveal_next(),
+			"ArrowLeft" | "PageUp" => hide_last(),
+			_ => {},
+		});
+		on_cleanup(move || handle.remove());
+	}
+
+	view! {
+		<div class="presentation">
+			<button
+				class="fullscreen-button"
+				on:click=move |_| {
+					if let Some(element) = document().document_element() {
+						let _ = element.request_fullscreen();
+					}
+				}
+			>
+				"Full screen"
+			</button>
+			<Transition
+				fallback=move || view! { <p>"Fetching election results..."</p> }
+			>
+				<ErrorBoundary fallback=|errors| view! { <ElectionErrors errors/> }>
+					{
+						Suspend::new(
+							async move {
+								let endpoints::Presentation { results, vote_link } = presentation.await?;
+								let n_ranks = results.ranked_candidates.len();
+								let candidates = results.candidates.clone();
+								let defeats_matrix = results.defeats_matrix.clone();
+								// the candidates each candidate beats head to head, with the votes for and against
+								let pairwise_wins = move |candidate: &String| {
+									let Some(winner) = candidates.iter().position(|other| other == candidate) else {
+										return Vec::new();
+									};
+									(0..candidates.len())
+										.filter_map(|loser| {
+											let votes_for = defeats_matrix[winner][loser]?;
+											let votes_against = defeats_matrix[loser][winner]?;
+											(votes_against < votes_for).then(|| {
+												format!("{} ({}–{})", candidates[loser], votes_for, votes_against)
+											})
+										})
+										.collect::<Vec<_>>()
+								};
+								Ok::<_, ServerFnError<FriendVoteError>>(view! {
+									<h1>{results.election_name}</h1>
+									<p class="presentation-vote-count">
+										{match results.n_votes {
+											1 => "1 vote".to_string(),
+											n_votes => format!("{} votes", n_votes),
+										}}
+									</p>
+									{vote_link.map(|vote_link| view! {
+										<div class="presentation-vote-link">
+											<div class="qr-code" inner_html=vote_link.qr_code></div>
+											<p>"Vote at " {vote_link.url}</p>
+										</div>
+									})}
+									<ol class="presentation-ranks">
+										{
+											results.ranked_candidates.into_iter().enumerate().map(|(rank, group)| {
+												let hidden = move || rank + n_revealed.get() < n_ranks;
+												let wins = group.iter().map(|candidate| (candidate.clone(), pairwise_wins(candidate))).collect::<Vec<_>>();
+												view! {
+													<li class:hidden=hidden class:winner=rank == 0>
+														<span class="rank">{rank + 1}</span>
+														{move || if hidden() {
+															view! { <span class="candidates">"?"</span> }.into_any()
+														} else {
+															view! {
+																<span class="candidates">{group.join(" / ")}</span>
+																<ul class="pairwise-wins">
+																	{wins.iter().filter(|(_, beaten)| !beaten.is_empty()).map(|(candidate, beaten)| view! {
+																		<li>{candidate.clone()} " beats " {beaten.join(", ")}</li>
+																	}).collect_view()}
+																</ul>
+															}.into_any()
+														}}
+													</li>
+												}
+											}).collect_view()
+										}
+									</ol>
+									<Show when=all_revealed>
+										<h4>"Defeats matrix:"</h4>
+										<table class="defeat-matrix">
+											<thead>
+												<tr>
+													<th scope="col"></th>
+													{
+														results.candidates.iter().map(
+															|cand| view!{
+																<th scope="col"><span>{cand.clone()}</span></th>
+															}
+														).collect_view()
+													}
+												</tr>
+											</thead>
+											<tbody>
+												{
+													results.defeats_matrix.iter().enumerate().map(
+														|(row_ind, defeats_row)| view!{
+															<tr>
+																<th scope="row">{results.candidates[row_ind].clone()}</th>
+																{
+																	defeats_row.iter().enumerate().map(
+																		|(col_ind, defeat)| {
+																			let won = defeat.zip(results.defeats_matrix[col_ind][row_ind]).is_some_and(|(votes_for, votes_against)| votes_against < votes_for);
+																			view! {
+																				<td class:win=won>{*defeat}</td>
+																			}
+																		}
+																	).collect_view()
+																}
+															</tr>
+														}
+													).collect_view()
+												}
+											</tbody>
+										</table>
+									</Show>
+								})
+							}
+						)
+					}
+				</ErrorBoundary>
+			</Transition>
+			<div class="presentation-controls">
+				<button on:click=move |_| hide_last() disabled=move || n_revealed.get() == 0>"Back"</button>
+				<button on:click=move |_| reveal_next() disabled=all_revealed>"Reveal next"</button>
+			</div>
+		</div>
+	}
+}
+
+#[component]
+fn NotFoundPage() -> impl IntoView {
+	#[cfg(feature = "ssr")]
+	if let Some(response_options) = use_context::<leptos_axum::ResponseOptions>() {
+		response_options.set_status(http::StatusCode::NOT_FOUND);
+	}
+
+	view! {
+		<h1>"Page not found"</h1>
+		<div style="text-align: center">
+			<p>"There is nothing at this address. Check that you copied the complete link."</p>
+			<p><a href="/">"Go to the home page"</a></p>
+		</div>
+	}
+}
+
+#[component]
+fn ElectionErrors(errors: ArcRwSignal<Errors>) -> impl IntoView {
+	let error_views = move || {
+		errors
+			.get()
+			.into_iter()
+			.map(|(_, error)| {
+				let error = match error.downcast_ref::<ServerFnError<FriendVoteError>>() {
+					Some(error) => FriendVoteError::from_server_fn_error(error),
+					None => FriendVoteError::Internal(error.to_string()),
+				};
+				view! { <ElectionError error/> }
+			})
+			.collect_view()
+	};
+
+	error_views
+}
+
+#[component]
+fn ElectionError(error: FriendVoteError) -> impl IntoView {
+	let (title, explanation) = match &error {
+		FriendVoteError::NotFound(_) => {
+			("Election not found", "This election does not exist. Check that you copied the complete link.")
+		},
+		FriendVoteError::Forbidden(_) => (
+			"Wrong link",
+			"The secret part of this link is not correct. Ask the election administrator for the right link.",
+		),
+		FriendVoteError::CannotVote(_) => ("Cannot vote", ""),
+		FriendVoteError::Closed(_) => ("Voting closed", "This election no longer accepts votes."),
+		FriendVoteError::TooManyRequests(_) => {
+			("Too many attempts", "Please wait a minute before trying again.")
+		},
+		FriendVoteError::Validation(_) => ("Invalid request", ""),
+		FriendVoteError::Internal(_) => ("Something went wrong", "Please try again later."),
+	};
+	let message = match &error {
+		FriendVoteError::Internal(_) => String::new(),
+		other => other.message().to_string(),
+	};
+
+	view! {
+		<h1>{title}</h1>
+		<div style="text-align: center">
+			<p>{message}</p>
+			<p>{explanation}</p>
+			<p><a href="/">"Go to the home page"</a></p>
+		</div>
 	}
 }
 
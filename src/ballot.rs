@@ -0,0 +1,208 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+/// A single voter's ranking of the candidates of an election.
+///
+/// Candidates are identified by their index in the election's candidate list.
+/// The groups are ordered from most to least preferred, and the candidates within a group are tied.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Ballot {
+	groups: Vec<Vec<usize>>,
+}
+
+impl Ballot {
+	/// Creates a ballot from groups of tied candidates, ordered from most to least preferred.
+	/// Empty groups are dropped.
+	pub fn new(groups: Vec<Vec<usize>>) -> Self {
+		let mut groups: Vec<Vec<usize>> = groups.into_iter().filter(|group| !group.is_empty()).collect();
+		for group in groups.iter_mut() {
+			group.sort_unstable();
+		}
+		Self { groups }
+	}
+
+	/// Creates a ballot from the candidates in order of preference,
+	/// and for each pair of consecutive candidates whether the first is strictly preferred over the second.
+	pub fn from_order(candidate_order: &[usize], comparator_is_bigger: &[bool]) -> Self {
+		let mut groups = Vec::new();
+		let mut group = Vec::new();
+		for (ind, candidate) in candidate_order.iter().enumerate() {
+			if ind > 0 && comparator_is_bigger.get(ind - 1).copied().unwrap_or(true) {
+				groups.push(group);
+				group = Vec::new();
+			}
+			group.push(*candidate);
+		}
+		groups.push(group);
+		Self::new(groups)
+	}
+
+	pub fn groups(&self) -> &Vec<Vec<usize>> {
+		&self.groups
+	}
+
+	pub fn n_candidates(&self) -> usize {
+		self.groups.iter().map(Vec::len).sum()
+	}
+
+	/// Encodes the ballot as text, e.g. `0>2=1>3` for candidate 0 first, then 1 and 2 tied, then 3.
+	///
+	/// Tied candidates are listed in increasing order, so equal ballots have equal encodings.
+	pub fn encode(&self) -> String {
+		self.groups
+			.iter()
+			.map(|group| group.iter().map(usize::to_string).collect::<Vec<_>>().join("="))
+			.collect::<Vec<_>>()
+			.join(">")
+	}
+
+	/// Parses the text produced by [`Ballot::encode`].
+	pub fn decode(encoded: &str) -> Option<Self> {
+		if encoded.is_empty() {
+			return Some(Self { groups: Vec::new() });
+		}
+		let mut groups = Vec::new();
+		for encoded_group in encoded.split('>') {
+			let mut group = Vec::new();
+			for encoded_candidate in encoded_group.split('=') {
+				group.push(encoded_candidate.parse::<usize>().ok()?);
+			}
+			groups.push(group);
+		}
+		Some(Self::new(groups))
+	}
+
+	/// Checks that every candidate of an election with `n_candidates` candidates appears exactly once.
+	pub fn is_complete(&self, n_candidates: usize) -> bool {
+		let mut seen = vec![false; n_candidates];
+		for candidate in self.groups.iter().flatten() {
+			match seen.get_mut(*candidate) {
+				Some(seen) if !*seen => *seen = true,
+				_ => return false,
+			}
+		}
+		seen.into_iter().all(|seen| seen)
+	}
+
+	/// Adds this ballot's pairwise preferences, `weight` times, to a square defeats matrix.
+	pub fn add_to_defeats_matrix(&self, defeats_matrix: &mut [Vec<Option<u64>>], weight: u64) {
+		for (group_ind, group) in self.groups.iter().enumerate() {
+			for winner in group {
+				for loser in self.groups[(group_ind + 1)..].iter().flatten() {
+					defeats_matrix[*winner][*loser] = defeats_matrix[*winner][*loser].map(|x| x + weight);
+				}
+			}
+		}
+	}
+}
+
+/// A distinct ranking, together with the number of voters who cast it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedBallot {
+	pub ballot: Ballot,
+	pub weight: u64,
+}
+
+impl WeightedBallot {
+	/// Groups identical ballots, most frequent first, and equally frequent ones by their encoding.
+	pub fn aggregate<'a>(ballots: impl IntoIterator<Item = &'a Ballot>) -> Vec<Self> {
+		let mut weights = std::collections::HashMap::<&Ballot, u64>::new();
+		for ballot in ballots {
+			*weights.entry(ballot).or_default() += 1;
+		}
+		let mut weighted_ballots = weights
+			.into_iter()
+			.map(|(ballot, weight)| Self {
+				ballot: ballot.clone(),
+				weight,
+			})
+			.collect::<Vec<_>>();
+		Self::sort(&mut weighted_ballots);
+		weighted_ballots
+	}
+
+	/// Sorts most frequent first, and equally frequent ballots by their encoding.
+	pub fn sort(weighted_ballots: &mut [Self]) {
+		weighted_ballots
+			.sort_by_cached_key(|weighted| (std::cmp::Reverse(weighted.weight), weighted.ballot.encode()));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn encoding_round_trips() {
+		for (groups, encoded) in [
+			(vec![vec![0], vec![1], vec![2]], "0>1>2"),
+			(vec![vec![0], vec![2, 1], vec![3]], "0>1=2>3"),
+			(vec![vec![3, 0, 2, 1]], "0=1=2=3"),
+			(vec![vec![12], vec![], vec![10]], "12>10"),
+			(vec![], ""),
+		] {
+			let ballot = Ballot::new(groups);
+			assert_eq!(ballot.encode(), encoded);
+			assert_eq!(Ballot::decode(encoded), Some(ballot));
+		}
+	}
+
+	#[test]
+	fn decoding_sorts_ties() {
+		assert_eq!(Ballot::decode("2=0>1"), Ballot::decode("0=2>1"));
+		assert_eq!(Ballot::decode("2=0>1").unwrap().encode(), "0=2>1");
+	}
+
+	#[test]
+	fn decoding_rejects_malformed_text() {
+		for encoded in ["0>", ">0", "0==1", "0>a", "-1", "0,1"] {
+			assert_eq!(Ballot::decode(encoded), None, "{encoded}");
+		}
+	}
+
+	#[test]
+	fn from_order_matches_groups() {
+		assert_eq!(Ballot::from_order(&[2, 0, 1], &[true, false]), Ballot::new(vec![vec![2], vec![0, 1]]));
+		assert_eq!(Ballot::from_order(&[1, 0, 2], &[false, false]), Ballot::new(vec![vec![0, 1, 2]]));
+		assert_eq!(Ballot::from_order(&[0, 1, 2], &[true, true]).encode(), "0>1>2");
+		assert_eq!(Ballot::from_order(&[], &[]), Ballot::new(vec![]));
+	}
+
+	#[test]
+	fn completeness_needs_every_candidate_once() {
+		assert!(Ballot::decode("1>0=2").unwrap().is_complete(3));
+		assert!(!Ballot::decode("1>0").unwrap().is_complete(3));
+		assert!(!Ballot::decode("1>0=1>2").unwrap().is_complete(3));
+		assert!(!Ballot::decode("1>0=3").unwrap().is_complete(3));
+	}
+
+	#[test]
+	fn aggregating_counts_identical_ballots() {
+		let ballots =
+			["0>1>2", "1>0=2", "2=0>1", "0>1>2", "0=2>1"].map(|encoded| Ballot::decode(encoded).unwrap());
+		assert_eq!(
+			WeightedBallot::aggregate(&ballots),
+			vec![
+				WeightedBallot {
+					ballot: Ballot::decode("0=2>1").unwrap(),
+					weight: 2,
+				},
+				WeightedBallot {
+					ballot: Ballot::decode("0>1>2").unwrap(),
+					weight: 2,
+				},
+				WeightedBallot {
+					ballot: Ballot::decode("1>0=2").unwrap(),
+					weight: 1,
+				},
+			]
+		);
+	}
+}
@@ -0,0 +1,67 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use leptos::prelude::window;
+
+use super::ListItem;
+
+/// Prefix of the local storage keys of the ballots in progress, followed by the election id.
+const DRAFT_KEY_PREFIX: &str = "friendvote-draft-";
+
+fn draft_key(election_id: &str) -> String {
+	format!("{}{}", DRAFT_KEY_PREFIX, election_id)
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+	window().local_storage().ok().flatten()
+}
+
+/// Keeps the ranking as arranged so far in the browser, so that it survives reloading the page.
+/// The candidates are stored by name, as their ids depend on the order they were shown in.
+pub fn save_draft(election_id: &str, items: &[ListItem]) {
+	let draft = items
+		.iter()
+		.map(|item| (item.label.as_str(), item.tied_with_next))
+		.collect::<Vec<_>>();
+	if let (Some(storage), Ok(draft)) = (local_storage(), serde_json::to_string(&draft)) {
+		// a full or disabled storage only means that the draft is lost on reload
+		let _ = storage.set_item(&draft_key(election_id), &draft);
+	}
+}
+
+/// Puts the items in the order of the saved draft, if there is one with exactly the same candidates.
+pub fn restore_draft(election_id: &str, items: &mut Vec<ListItem>) {
+	let Some(draft) = local_storage()
+		.and_then(|storage| storage.get_item(&draft_key(election_id)).ok().flatten())
+		.and_then(|draft| serde_json::from_str::<Vec<(String, bool)>>(&draft).ok())
+	else {
+		return;
+	};
+	if draft.len() != items.len() {
+		return;
+	}
+	let mut remaining = items.clone();
+	let mut restored = Vec::with_capacity(items.len());
+	for (label, tied_with_next) in draft {
+		let Some(position) = remaining.iter().position(|item| item.label == label) else {
+			return;
+		};
+		let mut item = remaining.remove(position);
+		item.tied_with_next = tied_with_next;
+		restored.push(item);
+	}
+	*items = restored;
+}
+
+pub fn clear_draft(election_id: &str) {
+	if let Some(storage) = local_storage() {
+		let _ = storage.remove_item(&draft_key(election_id));
+	}
+}
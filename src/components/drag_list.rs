@@ -0,0 +1,418 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use leptos::ev::{KeyboardEvent, PointerEvent};
+use leptos::prelude::*;
+use wasm_bindgen::JsCast;
+
+use super::ListItem;
+
+/// Whether the item at `position` is the last of its group of tied items.
+/// The last item of the list ends its group, whatever its tie flag says.
+fn ends_rank(items: &[ListItem], position: usize) -> bool {
+	position + 1 == items.len() || !items[position].tied_with_next
+}
+
+/// Moves the item at `from` to `to`, keeping groups of tied items together:
+/// the moved item leaves its group, and joins the group it is dropped into, if any.
+pub fn move_item(items: &mut Vec<ListItem>, from: usize, to: usize) {
+	if from == to || items.len() <= from || items.len() <= to {
+		return;
+	}
+	let mut item = items.remove(from);
+	// the item before it was only tied to the rest of the group through it
+	if 0 < from {
+		items[from - 1].tied_with_next &= item.tied_with_next;
+	}
+	item.tied_with_next = 0 < to && items[to - 1].tied_with_next;
+	items.insert(to, item);
+}
+
+/// Moves the item at `position` one rank up or down, and returns its new position.
+/// An item that is tied with others first leaves its group, on the side it moves to;
+/// an item on its own joins the neighbouring group.
+pub fn step_item(items: &mut Vec<ListItem>, position: usize, up: bool) -> usize {
+	if items.len() <= position {
+		return position;
+	}
+	let tied_with_next = !ends_rank(items, position);
+	let tied_with_previous = 0 < position && !ends_rank(items, position - 1);
+	if !tied_with_next && !tied_with_previous {
+		if up && 0 < position {
+			items[position - 1].tied_with_next = true;
+		} else if !up && position + 1 < items.len() {
+			items[position].tied_with_next = true;
+		}
+		return position;
+	}
+	let to = if up {
+		(0..position)
+			.rev()
+			.find(|&i| ends_rank(items, i))
+			.map_or(0, |i| i + 1)
+	} else {
+		// after the item is taken out, the group ends one place earlier
+		(position..items.len())
+			.find(|&i| ends_rank(items, i))
+			.unwrap_or(position)
+	};
+	let mut item = items.remove(position);
+	if 0 < position {
+		items[position - 1].tied_with_next &= tied_with_next;
+	}
+	if !up && 0 < to {
+		items[to - 1].tied_with_next = false;
+	}
+	item.tied_with_next = false;
+	items.insert(to, item);
+	to
+}
+
+/// Where the item at `position` stands in the ranking, in words.
+pub fn describe_rank(items: &[ListItem], position: usize) -> String {
+	let rank = 1 + (0..position).filter(|&i| ends_rank(items, i)).count();
+	let n_ranks = (0..items.len()).filter(|&i| ends_rank(items, i)).count();
+	let start = (0..position)
+		.rev()
+		.find(|&i| ends_rank(items, i))
+		.map_or(0, |i| i + 1);
+	let end = (position..items.len())
+		.find(|&i| ends_rank(items, i))
+		.unwrap_or(position);
+	let tied_with = (start..=end)
+		.filter(|&i| i != position)
+		.map(|i| items[i].label.as_str())
+		.collect::<Vec<_>>();
+	let mut description = format!("{}, rank {} of {}", items[position].label, rank, n_ranks);
+	if !tied_with.is_empty() {
+		description.push_str(", tied with ");
+		description.push_str(&tied_with.join(" and "));
+	}
+	description
+}
+
+/// How the item at `position` compares to the next one, in words.
+fn describe_tie(items: &[ListItem], position: usize) -> String {
+	let Some(next) = items.get(position + 1) else {
+		return String::new();
+	};
+	let comparison = if items[position].tied_with_next {
+		"tied with"
+	} else {
+		"preferred over"
+	};
+	format!("{} {} {}", items[position].label, comparison, next.label)
+}
+
+/// The id of the list item under the pointer, if any.
+fn item_id_at(event: &PointerEvent) -> Option<usize> {
+	document()
+		.element_from_point(event.client_x() as f32, event.client_y() as f32)?
+		.closest("[data-item-id]")
+		.ok()
+		.flatten()?
+		.get_attribute("data-item-id")?
+		.parse()
+		.ok()
+}
+
+/// Whether the event started on an element matching `selector`, inside the element that handles it.
+fn starts_within(event: &PointerEvent, selector: &str) -> bool {
+	event
+		.target()
+		.and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+		.and_then(|target| target.closest(selector).ok().flatten())
+		.is_some()
+}
+
+/// Moves the keyboard focus to the option of the item with this id.
+fn focus_option(id: usize) {
+	if let Some(option) = document()
+		.query_selector(&format!("[data-item-id=\"{}\"] [role=option]", id))
+		.ok()
+		.flatten()
+		.and_then(|option| option.dyn_into::<web_sys::HtmlElement>().ok())
+	{
+		let _ = option.focus();
+	}
+}
+
+/// A ranking that is reordered by dragging its items, with the mouse or by touch, or with the keyboard.
+/// The toggle after each item ties it with the next one.
+/// It follows the listbox pattern, and announces every change in a live region for screen readers.
+#[component]
+pub fn DragList(items: RwSignal<Vec<ListItem>>) -> impl IntoView {
+	// the id of the item being dragged
+	let dragged = RwSignal::new(None::<usize>);
+	// the id of the item that is reached with the tab key
+	let active = RwSignal::new(items.with_untracked(|items| items.first().map(|item| item.id)));
+	let announcement = RwSignal::new(String::new());
+	let position = move |id: usize| items.with_untracked(|items| items.iter().position(|item| item.id == id));
+	let tied_with_next = move |id: usize| {
+		items.with(|items| {
+			items
+				.iter()
+				.find(|item| item.id == id)
+				.is_some_and(|item| item.tied_with_next)
+		})
+	};
+	let next_label = move |id: usize| {
+		items.with(|items| {
+			let position = items.iter().position(|item| item.id == id)?;
+			items.get(position + 1).map(|next| next.label.clone())
+		})
+	};
+	let announce_rank = move |id: usize| {
+		if let Some(position) = position(id) {
+			announcement.set(items.with_untracked(|items| describe_rank(items, position)));
+		}
+	};
+	let toggle_tie = move |id: usize| {
+		if let Some(position) = position(id) {
+			items.update(|items| items[position].tied_with_next ^= true);
+			announcement.set(items.with_untracked(|items| describe_tie(items, position)));
+		}
+	};
+
+	let on_pointer_down = move |event: PointerEvent, id: usize| {
+		// touches elsewhere than on the handle scroll the page, and the tie toggle is clicked, not dragged
+		let is_mouse = event.pointer_type() == "mouse";
+		if event.button() != 0
+			|| (!is_mouse && !starts_within(&event, ".drag-handle"))
+			|| starts_within(&event, ".tie-toggle")
+		{
+			return;
+		}
+		event.prevent_default();
+		dragged.set(Some(id));
+	};
+	// the pointer leaves the dragged item as soon as it moves, so follow it on the whole window
+	let move_handle = window_event_listener(leptos::ev::pointermove, move |event| {
+		let Some(dragged_id) = dragged.get_untracked() else {
+			return;
+		};
+		let Some(over_id) = item_id_at(&event).filter(|over_id| *over_id != dragged_id) else {
+			return;
+		};
+		if let (Some(from), Some(to)) = (position(dragged_id), position(over_id)) {
+			items.update(|items| move_item(items, from, to));
+		}
+	});
+	let up_handle = window_event_listener(leptos::ev::pointerup, move |_| {
+		if let Some(id) = dragged.get_untracked() {
+			dragged.set(None);
+			announce_rank(id);
+		}
+	});
+	let cancel_handle = window_event_listener(leptos::ev::pointercancel, move |_| dragged.set(None));
+	on_cleanup(move || {
+		move_handle.remove();
+		up_handle.remove();
+		cancel_handle.remove();
+	});
+
+	let on_key_down = move |event: KeyboardEvent, id: usize| {
+		let Some(position) = position(id) else {
+			return;
+		};
+		let n_items = items.with_untracked(Vec::len);
+		let id_at = move |position: usize| items.with_untracked(|items| items[position].id);
+		match event.key().as_str() {
+			key @ ("ArrowUp" | "ArrowDown") if event.alt_key() => {
+				items.update(|items| {
+					step_item(items, position, key == "ArrowUp");
+				});
+				announce_rank(id);
+				// moving the element in the page takes the focus away from it
+				request_animation_frame(move || focus_option(id));
+			},
+			"ArrowUp" if 0 < position => focus_option(id_at(position - 1)),
+			"ArrowDown" if position + 1 < n_items => focus_option(id_at(position + 1)),
+			"Home" => focus_option(id_at(0)),
+			"End" => focus_option(id_at(n_items - 1)),
+			"t" | "T" if position + 1 < n_items => toggle_tie(id),
+			_ => return,
+		}
+		event.prevent_default();
+	};
+
+	view! {
+		<p id="drag-list-instructions" class="drag-list-instructions">
+			"Drag the candidates into your order of preference. "
+			"With the keyboard, choose a candidate with the arrow keys, move it with Alt and the arrow keys, "
+			"and press T to tie it with the next one."
+		</p>
+		<ol class="drag-list" role="listbox" aria-label="Your ranking" aria-describedby="drag-list-instructions">
+			<For
+				each=move || items.get()
+				key=|item| item.id
+				children=move |item| {
+					let id = item.id;
+					let toggle_id = format!("tie_toggle_{}", id);
+					let label = item.label.clone();
+					view! {
+						<li
+							class="drag-list-item"
+							class:dragging=move || dragged.get() == Some(id)
+							class:tied-with-next=move || tied_with_next(id)
+							role="none"
+							data-item-id=id
+							on:pointerdown=move |event| on_pointer_down(event, id)
+						>
+							<span class="drag-handle" aria-hidden="true">"⠿"</span>
+							<span
+								class="drag-list-label"
+								role="option"
+								tabindex=move || if active.get() == Some(id) { "0" } else { "-1" }
+								aria-selected=move || (active.get() == Some(id)).to_string()
+								aria-posinset=move || items.with(|items| items.iter().position(|item| item.id == id)).map(|position| position + 1)
+								aria-setsize=move || items.with(Vec::len)
+								on:focus=move |_| active.set(Some(id))
+								on:keydown=move |event| on_key_down(event, id)
+							>
+								{item.label}
+							</span>
+							// the last candidate has nothing to be tied with
+							<span class="tie-toggle" hidden=move || next_label(id).is_none()>
+								<input
+									id=toggle_id.clone()
+									class="comp-checkbox"
+									type="checkbox"
+									aria-label=move || format!("Prefer {} over {}", label, next_label(id).unwrap_or_default())
+									// the attribute for the server-rendered page, the property once it runs
+									checked=!item.tied_with_next
+									prop:checked=move || !tied_with_next(id)
+									on:change=move |_| toggle_tie(id)
+								/>
+								<label for=toggle_id title="Preferred over the next candidate, or tied with it"></label>
+							</span>
+						</li>
+					}
+				}
+			/>
+		</ol>
+		<p class="visually-hidden" aria-live="polite">{move || announcement.get()}</p>
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::components::ranking_in_words;
+
+	/// The items of a ranking written like "A > B = C".
+	fn items(ranking: &str) -> Vec<ListItem> {
+		let mut items: Vec<ListItem> = Vec::new();
+		for group in ranking.split(" > ") {
+			for (ind, label) in group.split(" = ").enumerate() {
+				if let Some(previous) = items.last_mut() {
+					previous.tied_with_next = 0 < ind;
+				}
+				items.push(ListItem {
+					id: items.len(),
+					label: label.to_string(),
+					tied_with_next: false,
+				});
+			}
+		}
+		items
+	}
+
+	fn moved(ranking: &str, from: usize, to: usize) -> String {
+		let mut items = items(ranking);
+		move_item(&mut items, from, to);
+		ranking_in_words(&items)
+	}
+
+	fn stepped(ranking: &str, position: usize, up: bool) -> (String, usize) {
+		let mut items = items(ranking);
+		let position = step_item(&mut items, position, up);
+		(ranking_in_words(&items), position)
+	}
+
+	#[test]
+	fn moved_items_keep_their_id() {
+		let mut items = items("A > B > C");
+		move_item(&mut items, 0, 2);
+		assert_eq!(items.iter().map(|item| item.id).collect::<Vec<_>>(), vec![1, 2, 0]);
+	}
+
+	#[test]
+	fn moved_items_join_the_group_they_are_dropped_into() {
+		assert_eq!(moved("A > B > C > D", 0, 3), "B > C > D > A");
+		assert_eq!(moved("A > B = C > D", 3, 2), "A > B = D = C");
+		assert_eq!(moved("A > B = C > D", 0, 1), "B = A = C > D");
+		// dropped after the end of a group, an item stays on its own
+		assert_eq!(moved("A > B = C > D", 0, 2), "B = C > A > D");
+	}
+
+	#[test]
+	fn moved_items_leave_their_group() {
+		// the rest of the group stays tied
+		assert_eq!(moved("A = B = C > D", 1, 3), "A = C > D > B");
+		assert_eq!(moved("A = B = C > D", 0, 3), "B = C > D > A");
+		// the item before the last one of a group now ends it
+		assert_eq!(moved("A > B = C", 2, 0), "C > A > B");
+		assert_eq!(moved("A = B", 1, 0), "B > A");
+	}
+
+	#[test]
+	fn moves_out_of_range_do_nothing() {
+		assert_eq!(moved("A > B = C", 1, 1), "A > B = C");
+		assert_eq!(moved("A > B = C", 3, 0), "A > B = C");
+		assert_eq!(moved("A > B = C", 0, 3), "A > B = C");
+		let mut empty = Vec::new();
+		move_item(&mut empty, 0, 0);
+		assert!(empty.is_empty());
+	}
+
+	#[test]
+	fn stepped_items_on_their_own_join_the_neighbouring_group() {
+		assert_eq!(stepped("A > B > C", 1, true), ("A = B > C".to_string(), 1));
+		assert_eq!(stepped("A > B > C", 1, false), ("A > B = C".to_string(), 1));
+		assert_eq!(stepped("A > B = C", 0, false), ("A = B = C".to_string(), 0));
+	}
+
+	#[test]
+	fn stepped_items_leave_their_group_on_the_side_they_move_to() {
+		assert_eq!(stepped("A > B = C = D", 2, true), ("A > C > B = D".to_string(), 1));
+		assert_eq!(stepped("A = B = C > D", 1, false), ("A = C > B > D".to_string(), 2));
+		// the first and last items of a group only need to leave it
+		assert_eq!(stepped("A = B > C", 0, true), ("A > B > C".to_string(), 0));
+		assert_eq!(stepped("A = B > C", 1, false), ("A > B > C".to_string(), 1));
+		assert_eq!(stepped("A > B = C", 2, false), ("A > B > C".to_string(), 2));
+	}
+
+	#[test]
+	fn stepping_past_the_ends_does_nothing() {
+		assert_eq!(stepped("A > B", 0, true), ("A > B".to_string(), 0));
+		assert_eq!(stepped("A > B", 1, false), ("A > B".to_string(), 1));
+		assert_eq!(stepped("A > B", 2, true), ("A > B".to_string(), 2));
+	}
+
+	#[test]
+	fn ranks_are_described_with_the_tied_items() {
+		let ranking = items("A > B = C > D");
+		assert_eq!(describe_rank(&ranking, 0), "A, rank 1 of 3");
+		assert_eq!(describe_rank(&ranking, 2), "C, rank 2 of 3, tied with B");
+		assert_eq!(describe_rank(&ranking, 3), "D, rank 3 of 3");
+		let ranking = items("A = B = C");
+		assert_eq!(describe_rank(&ranking, 1), "B, rank 1 of 1, tied with A and C");
+	}
+
+	#[test]
+	fn the_last_item_ends_its_group_whatever_its_tie_flag() {
+		let mut ranking = items("A > B");
+		ranking[1].tied_with_next = true;
+		assert_eq!(describe_rank(&ranking, 1), "B, rank 2 of 2");
+		assert_eq!(step_item(&mut ranking, 1, false), 1);
+		assert_eq!(describe_rank(&ranking, 0), "A, rank 1 of 2");
+	}
+}
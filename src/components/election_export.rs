@@ -0,0 +1,175 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use sha2::Sha256;
+
+use super::my_elections::MyElection;
+use crate::errors::FriendVoteError;
+
+/// Identifies export files, so that importing another file fails with a clear message.
+const EXPORT_FORMAT: &str = "friendvote-my-elections";
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+
+/// The elections, encrypted with a key derived from a passphrase.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExportFile {
+	format: String,
+	iterations: u32,
+	salt: String,
+	nonce: String,
+	ciphertext: String,
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], FriendVoteError> {
+	let mut bytes = [0; N];
+	getrandom::getrandom(&mut bytes)
+		.map_err(|error| FriendVoteError::Internal(format!("No randomness available: {}", error)))?;
+	Ok(bytes)
+}
+
+fn cipher(passphrase: &str, salt: &[u8], iterations: u32) -> Aes256Gcm {
+	let mut key = [0; 32];
+	pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+	Aes256Gcm::new(&key.into())
+}
+
+/// Encrypts the elections with AES-GCM, under a key derived from the passphrase with PBKDF2,
+/// into the contents of an export file.
+pub fn export_elections(elections: &[MyElection], passphrase: &str) -> Result<String, FriendVoteError> {
+	if passphrase.is_empty() {
+		return Err(FriendVoteError::Validation("Choose a passphrase to protect the export.".to_string()));
+	}
+	let salt = random_bytes::<SALT_LENGTH>()?;
+	let nonce = random_bytes::<NONCE_LENGTH>()?;
+	let plaintext =
+		serde_json::to_vec(elections).map_err(|error| FriendVoteError::Internal(error.to_string()))?;
+	let ciphertext = cipher(passphrase, &salt, PBKDF2_ITERATIONS)
+		.encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+		.map_err(|_| FriendVoteError::Internal("Could not encrypt the elections.".to_string()))?;
+	serde_json::to_string(&ExportFile {
+		format: EXPORT_FORMAT.to_string(),
+		iterations: PBKDF2_ITERATIONS,
+		salt: BASE64.encode(salt),
+		nonce: BASE64.encode(nonce),
+		ciphertext: BASE64.encode(ciphertext),
+	})
+	.map_err(|error| FriendVoteError::Internal(error.to_string()))
+}
+
+/// Decrypts the elections from the contents of an export file.
+pub fn import_elections(contents: &str, passphrase: &str) -> Result<Vec<MyElection>, FriendVoteError> {
+	let not_an_export =
+		|| FriendVoteError::Validation("This is not a FriendVote elections export.".to_string());
+	let export = serde_json::from_str::<ExportFile>(contents).map_err(|_| not_an_export())?;
+	// the number of iterations comes from the file, so a huge one must not hang the page
+	if export.format != EXPORT_FORMAT || !(1..=10 * PBKDF2_ITERATIONS).contains(&export.iterations) {
+		return Err(not_an_export());
+	}
+	let salt = BASE64.decode(export.salt).map_err(|_| not_an_export())?;
+	let nonce = BASE64.decode(export.nonce).map_err(|_| not_an_export())?;
+	let ciphertext = BASE64.decode(export.ciphertext).map_err(|_| not_an_export())?;
+	if nonce.len() != NONCE_LENGTH {
+		return Err(not_an_export());
+	}
+	let plaintext = cipher(passphrase, &salt, export.iterations)
+		.decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+		.map_err(|_| FriendVoteError::Forbidden("Wrong passphrase, or the file was changed.".to_string()))?;
+	serde_json::from_slice(&plaintext).map_err(|_| not_an_export())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::LazyLock;
+
+	use super::*;
+
+	fn elections() -> Vec<MyElection> {
+		vec![
+			MyElection {
+				election_id: "e1".to_string(),
+				admin_id: "a1".to_string(),
+				name: "Lunch".to_string(),
+			},
+			MyElection {
+				election_id: "e2".to_string(),
+				admin_id: "a2".to_string(),
+				name: "Dinner: with \"quotes\"".to_string(),
+			},
+		]
+	}
+
+	/// Deriving the key is slow without optimizations, so the tests share one export.
+	static EXPORT: LazyLock<String> =
+		LazyLock::new(|| export_elections(&elections(), "correct horse").unwrap());
+
+	/// Changes the export file, as a JSON value.
+	fn tamper(contents: &str, change: impl FnOnce(&mut serde_json::Value)) -> String {
+		let mut export = serde_json::from_str(contents).unwrap();
+		change(&mut export);
+		export.to_string()
+	}
+
+	#[test]
+	fn exports_can_be_imported_with_the_passphrase() {
+		assert!(!EXPORT.contains("Lunch"));
+		assert_eq!(import_elections(&EXPORT, "correct horse"), Ok(elections()));
+	}
+
+	#[test]
+	fn exports_have_their_own_salt_and_nonce() {
+		let export = serde_json::from_str::<ExportFile>(&EXPORT).unwrap();
+		let other =
+			serde_json::from_str::<ExportFile>(&export_elections(&elections(), "correct horse").unwrap())
+				.unwrap();
+		assert_ne!(export.salt, other.salt);
+		assert_ne!(export.nonce, other.nonce);
+	}
+
+	#[test]
+	fn exports_need_the_passphrase() {
+		let error = import_elections(&EXPORT, "wrong horse").unwrap_err();
+		assert!(matches!(error, FriendVoteError::Forbidden(_)));
+		let error = export_elections(&elections(), "").unwrap_err();
+		assert!(matches!(error, FriendVoteError::Validation(_)));
+	}
+
+	#[test]
+	fn changed_exports_are_refused() {
+		let tampered = tamper(&EXPORT, |export| {
+			let mut ciphertext = BASE64.decode(export["ciphertext"].as_str().unwrap()).unwrap();
+			ciphertext[0] ^= 1;
+			export["ciphertext"] = BASE64.encode(ciphertext).into();
+		});
+		let error = import_elections(&tampered, "correct horse").unwrap_err();
+		assert!(matches!(error, FriendVoteError::Forbidden(_)));
+	}
+
+	#[test]
+	fn iteration_counts_outside_the_accepted_range_are_refused() {
+		for iterations in [0, 10 * PBKDF2_ITERATIONS + 1, u32::MAX] {
+			let tampered = tamper(&EXPORT, |export| export["iterations"] = iterations.into());
+			let error = import_elections(&tampered, "correct horse").unwrap_err();
+			assert!(matches!(error, FriendVoteError::Validation(_)), "{iterations}");
+		}
+	}
+
+	#[test]
+	fn other_files_are_not_exports() {
+		for contents in ["", "[]", "{\"format\": \"friendvote-my-elections\"}"] {
+			let error = import_elections(contents, "correct horse").unwrap_err();
+			assert!(matches!(error, FriendVoteError::Validation(_)), "{contents}");
+		}
+	}
+}
@@ -0,0 +1,42 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+pub mod draft;
+pub mod drag_list;
+// exports are encrypted and decrypted in the browser only
+#[cfg(any(test, feature = "hydrate"))]
+pub mod election_export;
+pub mod my_elections;
+pub mod pairwise_questions;
+pub mod passkeys;
+pub mod rank_select;
+
+/// An entry of a ranking: a candidate, and whether it is tied with the next entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListItem {
+	/// Stays the same when the entry moves, unlike its position.
+	pub id: usize,
+	pub label: String,
+	pub tied_with_next: bool,
+}
+
+/// The ranking in plain words, such as "A > B = C > D".
+pub fn ranking_in_words(items: &[ListItem]) -> String {
+	let mut words = String::new();
+	for (position, item) in items.iter().enumerate() {
+		if 0 < position && items[position - 1].tied_with_next {
+			words.push_str(" = ");
+		} else if 0 < position {
+			words.push_str(" > ");
+		}
+		words.push_str(&item.label);
+	}
+	words
+}
@@ -0,0 +1,209 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use leptos::prelude::*;
+#[cfg(feature = "hydrate")]
+use wasm_bindgen::JsCast;
+
+#[cfg(feature = "hydrate")]
+use super::election_export::{export_elections, import_elections};
+use crate::errors::FriendVoteError;
+
+/// Local storage key of the elections created in this browser.
+const MY_ELECTIONS_KEY: &str = "friendvote-my-elections";
+
+/// An election created in this browser, with the credentials to manage it.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MyElection {
+	pub election_id: String,
+	pub admin_id: String,
+	pub name: String,
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+	window().local_storage().ok().flatten()
+}
+
+/// The elections created in this browser, the most recently visited first.
+pub fn load_my_elections() -> Vec<MyElection> {
+	local_storage()
+		.and_then(|storage| storage.get_item(MY_ELECTIONS_KEY).ok().flatten())
+		.and_then(|elections| serde_json::from_str(&elections).ok())
+		.unwrap_or_default()
+}
+
+fn save_my_elections(elections: &[MyElection]) {
+	if let (Some(storage), Ok(elections)) = (local_storage(), serde_json::to_string(elections)) {
+		let _ = storage.set_item(MY_ELECTIONS_KEY, &elections);
+	}
+}
+
+/// Adds the election to the elections created in this browser, or moves it to the top if it is there already.
+pub fn remember_election(election: MyElection) {
+	let mut elections = load_my_elections();
+	elections.retain(|known| known.election_id != election.election_id);
+	elections.insert(0, election);
+	save_my_elections(&elections);
+}
+
+#[cfg(feature = "hydrate")]
+/// Offers the contents as a file to save.
+fn download(file_name: &str, contents: &str) -> Result<(), FriendVoteError> {
+	let failed = |_| FriendVoteError::Internal("Could not save the export file.".to_string());
+	let parts = js_sys::Array::of1(&contents.into());
+	let options = web_sys::BlobPropertyBag::new();
+	options.set_type("application/json");
+	let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).map_err(failed)?;
+	let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(failed)?;
+	let link = document()
+		.create_element("a")
+		.map_err(failed)?
+		.unchecked_into::<web_sys::HtmlAnchorElement>();
+	link.set_href(&url);
+	link.set_download(file_name);
+	link.click();
+	web_sys::Url::revoke_object_url(&url).map_err(failed)
+}
+
+/// The elections created in this browser, with links to their admin pages,
+/// and an encrypted export file to take them to another device.
+#[component]
+pub fn MyElections() -> impl IntoView {
+	// local storage is only there in the browser, so the list is filled in once the page runs there
+	let elections = RwSignal::new(Vec::<MyElection>::new());
+	Effect::new(move |_| elections.set(load_my_elections()));
+	let passphrase = RwSignal::new(String::new());
+	let message = RwSignal::new(None::<Result<String, FriendVoteError>>);
+
+	let forget = move |election_id: String| {
+		elections.update(|elections| elections.retain(|election| election.election_id != election_id));
+		elections.with_untracked(|elections| save_my_elections(elections));
+	};
+	let export = move |_| {
+		#[cfg(feature = "hydrate")]
+		{
+			let result = elections
+				.with_untracked(|elections| export_elections(elections, &passphrase.get_untracked()))
+				.and_then(|contents| download("friendvote-elections.json", &contents))
+				.map(|()| {
+					"Exported. Import the file on the other device, with the same passphrase.".to_string()
+				});
+			message.set(Some(result));
+		}
+	};
+	let import = move |input: web_sys::HtmlInputElement| {
+		#[cfg(feature = "hydrate")]
+		{
+			let Some(file) = input.files().and_then(|files| files.get(0)) else {
+				return;
+			};
+			// so that choosing the same file again, with another passphrase, imports it again
+			input.set_value("");
+			leptos::task::spawn_local(async move {
+				let contents = wasm_bindgen_futures::JsFuture::from(file.text())
+					.await
+					.ok()
+					.and_then(|contents| contents.as_string())
+					.ok_or_else(|| FriendVoteError::Validation("Could not read the file.".to_string()));
+				let result = contents
+					.and_then(|contents| import_elections(&contents, &passphrase.get_untracked()))
+					.map(|imported| {
+						let mut known = load_my_elections();
+						let n_known = known.len();
+						for election in imported {
+							if !known
+								.iter()
+								.any(|other| other.election_id == election.election_id)
+							{
+								known.push(election);
+							}
+						}
+						let n_new = known.len() - n_known;
+						save_my_elections(&known);
+						elections.set(known);
+						format!(
+							"Imported {} new election{}.",
+							n_new,
+							if n_new == 1 {
+								""
+							} else {
+								"s"
+							}
+						)
+					});
+				message.set(Some(result));
+			});
+		}
+		#[cfg(not(feature = "hydrate"))]
+		let _ = input;
+	};
+
+	view! {
+		<section class="my-elections">
+			<h2>"My elections"</h2>
+			<Show
+				when=move || elections.with(|elections| !elections.is_empty())
+				fallback=|| view! { <p>"Elections you create in this browser are listed here."</p> }
+			>
+				<ul>
+					<For
+						each=move || elections.get()
+						key=|election| election.election_id.clone()
+						children=move |election| {
+							let election_id = election.election_id.clone();
+							view! {
+								<li>
+									<a href=format!("/{}/admin/{}", election.election_id, election.admin_id)>{election.name}</a>
+									<button type="button" on:click=move |_| forget(election_id.clone())>"Forget"</button>
+								</li>
+							}
+						}
+					/>
+				</ul>
+			</Show>
+			<details class="my-elections-transfer">
+				<summary>"Move my elections to another device"</summary>
+				<p>"The export file holds the admin links, encrypted with your passphrase."</p>
+				<div>
+					<label for="my_elections_passphrase">"Passphrase: "</label>
+					<input
+						id="my_elections_passphrase"
+						type="password"
+						autocomplete="new-password"
+						prop:value=passphrase
+						on:input:target=move |event| passphrase.set(event.target().value())
+					/>
+				</div>
+				<div>
+					<button
+						type="button"
+						disabled=move || elections.with(Vec::is_empty)
+						on:click=export
+					>
+						"Export"
+					</button>
+				</div>
+				<div>
+					<label for="my_elections_import">"Import a file: "</label>
+					<input
+						id="my_elections_import"
+						type="file"
+						accept=".json,application/json"
+						on:change:target=move |event| import(event.target())
+					/>
+				</div>
+				{move || message.get().map(|message| match message {
+					Ok(message) => view! { <p>{message}</p> }.into_any(),
+					Err(error) => view! { <p class="error">{error.message().to_string()}</p> }.into_any(),
+				})}
+			</details>
+		</section>
+	}
+}
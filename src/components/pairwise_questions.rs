@@ -0,0 +1,413 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+
+use leptos::prelude::*;
+
+use super::ListItem;
+
+/// Which of two candidates the voter prefers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preference {
+	First,
+	Second,
+	Equal,
+}
+
+/// A question the voter answered: the ids of the two candidates, and the preference between them.
+pub type Answer = (usize, usize, Preference);
+
+/// What the answers so far tell about the candidates, which are referred to by their index in `ids`:
+/// which ones are tied, and which groups of tied candidates are preferred over which others.
+struct Knowledge<'a> {
+	ids: &'a [usize],
+	/// For each candidate, the first candidate of its group of tied candidates.
+	group: Vec<usize>,
+	/// For each group, the groups that the voter prefers it over directly.
+	preferred_over: Vec<Vec<usize>>,
+}
+
+impl<'a> Knowledge<'a> {
+	fn new(ids: &'a [usize], answers: &[Answer]) -> Self {
+		let index_of = ids
+			.iter()
+			.enumerate()
+			.map(|(index, &id)| (id, index))
+			.collect::<HashMap<_, _>>();
+		let answers = answers
+			.iter()
+			.filter_map(|&(first, second, preference)| {
+				Some((*index_of.get(&first)?, *index_of.get(&second)?, preference))
+			})
+			.collect::<Vec<_>>();
+
+		let mut group = (0..ids.len()).collect::<Vec<_>>();
+		for &(first, second, preference) in &answers {
+			if preference == Preference::Equal {
+				let (kept, merged) = (group[first].min(group[second]), group[first].max(group[second]));
+				group
+					.iter_mut()
+					.filter(|group| **group == merged)
+					.for_each(|group| *group = kept);
+			}
+		}
+		let mut preferred_over = vec![Vec::new(); ids.len()];
+		for &(first, second, preference) in &answers {
+			match preference {
+				Preference::First => preferred_over[group[first]].push(group[second]),
+				Preference::Second => preferred_over[group[second]].push(group[first]),
+				Preference::Equal => {},
+			}
+		}
+		Self {
+			ids,
+			group,
+			preferred_over,
+		}
+	}
+
+	/// Whether the answers imply that the voter prefers group `better` over group `worse`, through any chain.
+	fn implies(&self, better: usize, worse: usize) -> bool {
+		let mut seen = vec![false; self.group.len()];
+		let mut stack = vec![better];
+		while let Some(group) = stack.pop() {
+			for &next in &self.preferred_over[group] {
+				if next == worse {
+					return true;
+				}
+				if !seen[next] {
+					seen[next] = true;
+					stack.push(next);
+				}
+			}
+		}
+		false
+	}
+
+	/// The ids of the candidates in a group.
+	fn members(&self, group: usize) -> Vec<usize> {
+		(0..self.ids.len())
+			.filter(|&index| self.group[index] == group)
+			.map(|index| self.ids[index])
+			.collect()
+	}
+
+	/// The ids of the candidates tied with the candidate with this id, itself first.
+	fn tied_with(&self, id: usize) -> Vec<usize> {
+		let Some(index) = self.ids.iter().position(|&other| other == id) else {
+			return vec![id];
+		};
+		let mut members = self.members(self.group[index]);
+		members.retain(|&member| member != id);
+		members.insert(0, id);
+		members
+	}
+}
+
+/// The order in which merge-insertion inserts the pending elements, where pending element `i`
+/// belongs with the `i + 2`th element of the main chain: in groups ending at the Jacobsthal numbers
+/// 3, 5, 11, 21, ..., each from its end, so that every binary search is over a chain just short of a power of two.
+fn insertion_order(n_pending: usize) -> Vec<usize> {
+	let mut order = Vec::with_capacity(n_pending);
+	let (mut previous, mut current) = (1, 3);
+	let mut n_inserted = 1;
+	while n_inserted < n_pending + 1 {
+		let last = current.min(n_pending + 1);
+		order.extend((n_inserted + 1..=last).rev().map(|element| element - 2));
+		n_inserted = last;
+		(previous, current) = (current, current + 2 * previous);
+	}
+	order
+}
+
+/// Sorts distinct elements with the Ford-Johnson merge-insertion algorithm, which needs close to
+/// the fewest comparisons possible. `before(a, b)` tells whether `a` comes before `b`,
+/// or fails with a question to ask first.
+fn merge_insertion<Q>(
+	elements: &[usize],
+	before: &mut impl FnMut(usize, usize) -> Result<bool, Q>,
+) -> Result<Vec<usize>, Q> {
+	if elements.len() <= 1 {
+		return Ok(elements.to_vec());
+	}
+	// pairs of (later, earlier) elements, of which the later ones are sorted first
+	let mut pairs = Vec::with_capacity(elements.len() / 2);
+	for pair in elements.chunks_exact(2) {
+		if before(pair[0], pair[1])? {
+			pairs.push((pair[1], pair[0]));
+		} else {
+			pairs.push((pair[0], pair[1]));
+		}
+	}
+	let later = pairs.iter().map(|pair| pair.0).collect::<Vec<_>>();
+	let mut chain = merge_insertion(&later, before)?;
+	let partner = |later: usize| {
+		pairs
+			.iter()
+			.find(|pair| pair.0 == later)
+			.map_or(later, |pair| pair.1)
+	};
+
+	// the partner of the first element of the chain comes before all of it
+	let mut pending = chain[1..]
+		.iter()
+		.map(|&later| (partner(later), Some(later)))
+		.collect::<Vec<_>>();
+	chain.insert(0, partner(chain[0]));
+	if elements.len() % 2 == 1 {
+		pending.push((elements[elements.len() - 1], None));
+	}
+	for index in insertion_order(pending.len()) {
+		let (element, partner) = pending[index];
+		// only the part of the chain before its partner is left to search
+		let mut high = partner.map_or(chain.len(), |partner| {
+			chain
+				.iter()
+				.position(|&chained| chained == partner)
+				.unwrap_or(chain.len())
+		});
+		let mut low = 0;
+		while low < high {
+			let middle = (low + high) / 2;
+			if before(element, chain[middle])? {
+				high = middle;
+			} else {
+				low = middle + 1;
+			}
+		}
+		chain.insert(low, element);
+	}
+	Ok(chain)
+}
+
+/// Replays the sort of the candidates with the voter's answers so far. Gives the next question to ask
+/// as the ids of two candidates, or once there is none left, the ranking as groups of tied candidate ids.
+pub fn next_question(ids: &[usize], answers: &[Answer]) -> Result<Vec<Vec<usize>>, (usize, usize)> {
+	let knowledge = Knowledge::new(ids, answers);
+	let groups = (0..ids.len())
+		.filter(|&index| knowledge.group[index] == index)
+		.collect::<Vec<_>>();
+	let order = merge_insertion(&groups, &mut |first, second| {
+		if knowledge.implies(first, second) {
+			Ok(true)
+		} else if knowledge.implies(second, first) {
+			Ok(false)
+		} else {
+			Err((ids[first], ids[second]))
+		}
+	})?;
+	Ok(order.into_iter().map(|group| knowledge.members(group)).collect())
+}
+
+/// A ballot built by asking the voter which of two candidates they prefer, over and over,
+/// which is less tiring than dragging for long lists of candidates.
+/// Once the ranking is known, it replaces `items`, and `asking` is turned off.
+#[component]
+pub fn PairwiseQuestions(items: RwSignal<Vec<ListItem>>, asking: RwSignal<bool>) -> impl IntoView {
+	let ids =
+		StoredValue::new(items.with_untracked(|items| items.iter().map(|item| item.id).collect::<Vec<_>>()));
+	let labels = StoredValue::new(items.with_untracked(|items| {
+		items
+			.iter()
+			.map(|item| (item.id, item.label.clone()))
+			.collect::<HashMap<_, _>>()
+	}));
+	let answers = RwSignal::new(Vec::<Answer>::new());
+	let question =
+		Memo::new(move |_| answers.with(|answers| ids.with_value(|ids| next_question(ids, answers).err())));
+	// candidates that were answered to be equal are asked about together
+	let label = move |id: usize| {
+		let tied_with =
+			answers.with(|answers| ids.with_value(|ids| Knowledge::new(ids, answers).tied_with(id)));
+		labels.with_value(|labels| {
+			tied_with
+				.iter()
+				.filter_map(|id| labels.get(id).cloned())
+				.collect::<Vec<_>>()
+				.join(" = ")
+		})
+	};
+
+	let answer = move |preference: Preference| {
+		let Some((first, second)) = question.get_untracked() else {
+			return;
+		};
+		answers.update(|answers| answers.push((first, second, preference)));
+		let Ok(groups) = answers.with_untracked(|answers| ids.with_value(|ids| next_question(ids, answers)))
+		else {
+			return;
+		};
+		items.update(|items| {
+			let mut by_id = items
+				.drain(..)
+				.map(|item| (item.id, item))
+				.collect::<HashMap<_, _>>();
+			for group in groups {
+				let n_tied = group.len();
+				for (position, id) in group.into_iter().enumerate() {
+					if let Some(mut item) = by_id.remove(&id) {
+						item.tied_with_next = position + 1 < n_tied;
+						items.push(item);
+					}
+				}
+			}
+		});
+		asking.set(false);
+	};
+
+	view! {
+		<div class="pairwise-questions">
+			{move || question.get().map(|(first, second)| view! {
+				<p aria-live="polite">"Question "{answers.with(Vec::len) + 1}": which do you prefer?"</p>
+				<div class="pairwise-choices">
+					<button type="button" on:click=move |_| answer(Preference::First)>{label(first)}</button>
+					<button type="button" on:click=move |_| answer(Preference::Equal)>"Equal"</button>
+					<button type="button" on:click=move |_| answer(Preference::Second)>{label(second)}</button>
+				</div>
+			})}
+			<button
+				type="button"
+				disabled=move || answers.with(Vec::is_empty)
+				on:click=move |_| answers.update(|answers| {
+					answers.pop();
+				})
+			>
+				"Undo the last answer"
+			</button>
+			<button type="button" on:click=move |_| asking.set(false)>"Back to the list"</button>
+		</div>
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The worst-case number of comparisons of merge-insertion: the sum of ⌈log₂(3k/4)⌉ for k up to n.
+	fn ford_johnson_bound(n: usize) -> usize {
+		(1..=n)
+			.map(|k| {
+				let mut comparisons = 0;
+				while (1 << comparisons) * 4 < 3 * k {
+					comparisons += 1;
+				}
+				comparisons
+			})
+			.sum()
+	}
+
+	fn permutations(n: usize) -> Vec<Vec<usize>> {
+		if n == 0 {
+			return vec![Vec::new()];
+		}
+		permutations(n - 1)
+			.into_iter()
+			.flat_map(|shorter| {
+				(0..n).map(move |position| {
+					let mut permutation = shorter.clone();
+					permutation.insert(position, n - 1);
+					permutation
+				})
+			})
+			.collect()
+	}
+
+	/// Asks the questions of `next_question` to a voter who gives the candidate with id `id` rank `rank(id)`,
+	/// lower being better. Returns the ranking and the number of questions asked.
+	fn ask(ids: &[usize], rank: impl Fn(usize) -> usize) -> (Vec<Vec<usize>>, usize) {
+		let mut answers = Vec::new();
+		loop {
+			match next_question(ids, &answers) {
+				Ok(ranking) => return (ranking, answers.len()),
+				Err((first, second)) => {
+					let preference = match rank(first).cmp(&rank(second)) {
+						std::cmp::Ordering::Less => Preference::First,
+						std::cmp::Ordering::Greater => Preference::Second,
+						std::cmp::Ordering::Equal => Preference::Equal,
+					};
+					answers.push((first, second, preference));
+				},
+			}
+		}
+	}
+
+	#[test]
+	fn bound_matches_known_values() {
+		let bounds = (1..=8).map(ford_johnson_bound).collect::<Vec<_>>();
+		assert_eq!(bounds, vec![0, 1, 3, 5, 7, 10, 13, 16]);
+	}
+
+	#[test]
+	fn insertion_order_follows_jacobsthal_groups() {
+		assert_eq!(insertion_order(0), Vec::<usize>::new());
+		assert_eq!(insertion_order(1), vec![0]);
+		assert_eq!(insertion_order(3), vec![1, 0, 2]);
+		assert_eq!(insertion_order(4), vec![1, 0, 3, 2]);
+		assert_eq!(insertion_order(10), vec![1, 0, 3, 2, 9, 8, 7, 6, 5, 4]);
+		for n_pending in 0..50 {
+			let mut order = insertion_order(n_pending);
+			order.sort_unstable();
+			assert_eq!(order, (0..n_pending).collect::<Vec<_>>());
+		}
+	}
+
+	#[test]
+	fn merge_insertion_sorts_within_the_bound() {
+		for n in 0..=8 {
+			for permutation in permutations(n) {
+				let mut n_comparisons = 0;
+				let sorted = merge_insertion(&permutation, &mut |a, b| {
+					n_comparisons += 1;
+					Ok::<_, ()>(a < b)
+				})
+				.unwrap();
+				assert_eq!(sorted, (0..n).collect::<Vec<_>>(), "{permutation:?}");
+				assert!(n_comparisons <= ford_johnson_bound(n), "{permutation:?}: {n_comparisons}");
+			}
+		}
+	}
+
+	#[test]
+	fn questions_find_a_strict_ranking_within_the_bound() {
+		for n in 1..=6 {
+			let ids = (0..n).map(|index| 10 * index).collect::<Vec<_>>();
+			for ranks in permutations(n) {
+				let (ranking, n_questions) = ask(&ids, |id| ranks[id / 10]);
+				let mut expected = ids.clone();
+				expected.sort_by_key(|id| ranks[id / 10]);
+				assert_eq!(ranking, expected.into_iter().map(|id| vec![id]).collect::<Vec<_>>());
+				assert!(n_questions <= ford_johnson_bound(n), "{ranks:?}: {n_questions}");
+			}
+		}
+	}
+
+	#[test]
+	fn equal_answers_tie_candidates() {
+		let ids = [3, 1, 4, 5, 9, 2];
+		let ranks = HashMap::from([(3, 2), (1, 1), (4, 2), (5, 3), (9, 1), (2, 2)]);
+		let (ranking, n_questions) = ask(&ids, |id| ranks[&id]);
+		assert_eq!(ranking, vec![vec![1, 9], vec![3, 4, 2], vec![5]]);
+		assert!(n_questions <= ford_johnson_bound(ids.len()));
+
+		// all equal
+		let (ranking, _) = ask(&ids, |_| 0);
+		assert_eq!(ranking, vec![ids.to_vec()]);
+	}
+
+	#[test]
+	fn tied_candidates_are_asked_about_together() {
+		let ids = [0, 1, 2];
+		let knowledge = Knowledge::new(&ids, &[(2, 0, Preference::Equal)]);
+		assert_eq!(knowledge.tied_with(0), vec![0, 2]);
+		assert_eq!(knowledge.tied_with(2), vec![2, 0]);
+		assert_eq!(knowledge.tied_with(1), vec![1]);
+	}
+}
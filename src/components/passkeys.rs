@@ -0,0 +1,141 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use leptos::prelude::*;
+#[cfg(feature = "hydrate")]
+use wasm_bindgen::{JsCast, JsValue};
+#[cfg(feature = "hydrate")]
+use wasm_bindgen_futures::JsFuture;
+#[cfg(feature = "hydrate")]
+use webauthn_rs_proto::{PublicKeyCredential, RegisterPublicKeyCredential};
+
+#[cfg(feature = "hydrate")]
+use crate::endpoints;
+use crate::errors::FriendVoteError;
+
+// passkeys are only asked for in the browser, the server renders the buttons
+#[cfg(feature = "hydrate")]
+fn credentials() -> web_sys::CredentialsContainer {
+	window().navigator().credentials()
+}
+
+#[cfg(feature = "hydrate")]
+/// Waits for the authenticator to answer a challenge, which it does once the user confirms.
+async fn ask_authenticator(
+	request: Result<js_sys::Promise, JsValue>,
+) -> Result<web_sys::PublicKeyCredential, FriendVoteError> {
+	let request = request
+		.map_err(|_| FriendVoteError::Validation("This browser does not support passkeys.".to_string()))?;
+	JsFuture::from(request)
+		.await
+		.ok()
+		.and_then(|credential| credential.dyn_into().ok())
+		.ok_or_else(|| FriendVoteError::Forbidden("No passkey was used.".to_string()))
+}
+
+#[cfg(feature = "hydrate")]
+async fn register_passkey() -> Result<(), FriendVoteError> {
+	let challenge = endpoints::start_passkey_registration()
+		.await
+		.map_err(|error| FriendVoteError::from_server_fn_error(&error))?;
+	let options = web_sys::CredentialCreationOptions::from(challenge.options);
+	let credential = ask_authenticator(credentials().create_with_options(&options)).await?;
+	endpoints::finish_passkey_registration(
+		challenge.ceremony_id,
+		RegisterPublicKeyCredential::from(credential),
+	)
+	.await
+	.map_err(|error| FriendVoteError::from_server_fn_error(&error))
+}
+
+#[cfg(feature = "hydrate")]
+async fn log_in_with_passkey(username: String) -> Result<(), FriendVoteError> {
+	let challenge = endpoints::start_passkey_login(username)
+		.await
+		.map_err(|error| FriendVoteError::from_server_fn_error(&error))?;
+	let options = web_sys::CredentialRequestOptions::from(challenge.options);
+	let credential = ask_authenticator(credentials().get_with_options(&options)).await?;
+	endpoints::finish_passkey_login(challenge.ceremony_id, PublicKeyCredential::from(credential))
+		.await
+		.map_err(|error| FriendVoteError::from_server_fn_error(&error))
+}
+
+/// Adds a passkey on this device to the logged in account, to log in without the password from then on.
+#[component]
+pub fn AddPasskey(n_passkeys: usize) -> impl IntoView {
+	let n_passkeys = RwSignal::new(n_passkeys);
+	let message = RwSignal::new(None::<Result<String, FriendVoteError>>);
+
+	let add = move |_| {
+		#[cfg(feature = "hydrate")]
+		leptos::task::spawn_local(async move {
+			let result = register_passkey().await.map(|()| {
+				n_passkeys.update(|n_passkeys| *n_passkeys += 1);
+				"Added the passkey. Next time, you can log in with it.".to_string()
+			});
+			message.set(Some(result));
+		});
+	};
+
+	view! {
+		<div class="passkeys">
+			<p>
+				{move || match n_passkeys.get() {
+					0 => "You have no passkeys yet.".to_string(),
+					1 => "You have 1 passkey.".to_string(),
+					n_passkeys => format!("You have {} passkeys.", n_passkeys),
+				}}
+			</p>
+			<button type="button" on:click=add>"Add a passkey"</button>
+			{move || message.get().map(|message| match message {
+				Ok(message) => view! { <p>{message}</p> }.into_any(),
+				Err(error) => view! { <p class="error">{error.message().to_string()}</p> }.into_any(),
+			})}
+		</div>
+	}
+}
+
+/// Logs in with a passkey, which needs JavaScript, unlike the password forms.
+/// Counts the logins in `logins`, so that the page can show the account.
+#[component]
+pub fn PasskeyLogin(logins: RwSignal<usize>) -> impl IntoView {
+	let username = RwSignal::new(String::new());
+	let error = RwSignal::new(None::<FriendVoteError>);
+
+	let log_in = move |_| {
+		#[cfg(feature = "hydrate")]
+		leptos::task::spawn_local(async move {
+			match log_in_with_passkey(username.get_untracked()).await {
+				Ok(()) => logins.update(|logins| *logins += 1),
+				Err(login_error) => error.set(Some(login_error)),
+			}
+		});
+		#[cfg(not(feature = "hydrate"))]
+		let _ = logins;
+	};
+
+	view! {
+		<div class="passkeys">
+			<h2>"Log in with a passkey"</h2>
+			<div>
+				<label for="passkey_username">"Username: "</label>
+				<input
+					id="passkey_username"
+					type="text"
+					autocomplete="username webauthn"
+					prop:value=username
+					on:input:target=move |event| username.set(event.target().value())
+				/>
+			</div>
+			<button type="button" on:click=log_in>"Log in with a passkey"</button>
+			{move || error.get().map(|error| view! { <p class="error">{error.message().to_string()}</p> })}
+		</div>
+	}
+}
@@ -0,0 +1,148 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use leptos::prelude::*;
+use leptos_router::params::ParamsMap;
+
+use crate::errors::FriendVoteError;
+
+/// A ballot that works without JavaScript: a rank to choose for each candidate, equal ranks meaning a tie.
+/// The ranks are posted as `ranks[<index>]`, next to the candidates as `candidates[<index>]`.
+#[component]
+pub fn RankSelectList(candidates: Vec<String>) -> impl IntoView {
+	let n_candidates = candidates.len();
+	view! {
+		<p class="rank-select-instructions">
+			"Give each candidate a rank, 1 for your favourite. Candidates with the same rank are tied."
+		</p>
+		<ol class="rank-select-list">
+			{candidates
+				.into_iter()
+				.enumerate()
+				.map(|(index, candidate)| {
+					let select_id = format!("rank_{}", index);
+					view! {
+						<li>
+							<label for=select_id.clone()>{candidate.clone()}</label>
+							<select id=select_id name=format!("ranks[{}]", index)>
+								{(1..=n_candidates)
+									.map(|rank| view! { <option value=rank selected=rank == index + 1>{rank}</option> })
+									.collect_view()}
+							</select>
+							<input type="hidden" name=format!("candidates[{}]", index) value=candidate/>
+						</li>
+					}
+				})
+				.collect_view()}
+		</ol>
+	}
+}
+
+/// Orders the candidates by the rank given to each of them, 1 being the most preferred,
+/// as posted by [`RankSelectList`]. Candidates with the same rank are tied, and unused ranks are skipped.
+///
+/// This is how the server reads ballots cast without JavaScript, in the `cast_vote` server function,
+/// so the ranks are untrusted form input. It lives next to the form, rather than the server code,
+/// because the page that reviews such a ballot before it is cast also needs it.
+pub fn order_from_ranks(
+	candidates: Vec<String>,
+	ranks: &[String],
+) -> Result<(Vec<String>, Vec<bool>), FriendVoteError> {
+	if ranks.len() != candidates.len() {
+		return Err(FriendVoteError::Validation(format!("Number of ranks does not match: {}", ranks.len())));
+	}
+	let mut ranked = ranks
+		.iter()
+		.zip(candidates)
+		.map(|(rank, candidate)| match rank.trim().parse::<usize>() {
+			Ok(rank) if 0 < rank => Ok((rank, candidate)),
+			_ => Err(FriendVoteError::Validation(format!("Illegal rank for {candidate}: {rank}"))),
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+	ranked.sort_by_key(|(rank, _)| *rank);
+	let comparator_is_bigger = ranked.windows(2).map(|pair| pair[0].0 < pair[1].0).collect();
+	Ok((ranked.into_iter().map(|(_, candidate)| candidate).collect(), comparator_is_bigger))
+}
+
+/// The candidates and ranks of a [`RankSelectList`] that was submitted to a page, as its query,
+/// or `None` if there are none.
+pub fn ranks_from_query(query: &ParamsMap) -> Option<(Vec<String>, Vec<String>)> {
+	let mut candidates = Vec::new();
+	let mut ranks = Vec::new();
+	while let (Some(candidate), Some(rank)) = (
+		query.get(&format!("candidates[{}]", candidates.len())),
+		query.get(&format!("ranks[{}]", ranks.len())),
+	) {
+		candidates.push(candidate);
+		ranks.push(rank);
+	}
+	(!candidates.is_empty()).then_some((candidates, ranks))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn order(candidates: &[&str], ranks: &[&str]) -> Result<(Vec<String>, Vec<bool>), FriendVoteError> {
+		order_from_ranks(
+			candidates.iter().map(|candidate| candidate.to_string()).collect(),
+			&ranks.iter().map(|rank| rank.to_string()).collect::<Vec<_>>(),
+		)
+	}
+
+	fn names(names: &[&str]) -> Vec<String> {
+		names.iter().map(|name| name.to_string()).collect()
+	}
+
+	#[test]
+	fn candidates_are_ordered_by_rank() {
+		assert_eq!(order(&["A", "B", "C"], &["2", "3", "1"]), Ok((names(&["C", "A", "B"]), vec![true, true])));
+		assert_eq!(
+			order(&["A", "B", "C"], &[" 3", "1 ", "2"]),
+			Ok((names(&["B", "C", "A"]), vec![true, true]))
+		);
+	}
+
+	#[test]
+	fn equal_ranks_are_ties() {
+		assert_eq!(
+			order(&["A", "B", "C"], &["2", "1", "2"]),
+			Ok((names(&["B", "A", "C"]), vec![true, false]))
+		);
+		assert_eq!(
+			order(&["A", "B", "C"], &["1", "1", "1"]),
+			Ok((names(&["A", "B", "C"]), vec![false, false]))
+		);
+	}
+
+	#[test]
+	fn unused_ranks_are_skipped() {
+		assert_eq!(
+			order(&["A", "B", "C"], &["3", "1", "3"]),
+			Ok((names(&["B", "A", "C"]), vec![true, false]))
+		);
+		// ranks beyond the number of candidates cannot be chosen in the form, but do no harm
+		assert_eq!(order(&["A", "B"], &["1", "7"]), Ok((names(&["A", "B"]), vec![true])));
+	}
+
+	#[test]
+	fn the_number_of_ranks_must_match_the_candidates() {
+		assert!(matches!(order(&["A", "B", "C"], &["1", "2"]), Err(FriendVoteError::Validation(_))));
+		assert!(matches!(order(&["A", "B"], &["1", "2", "3"]), Err(FriendVoteError::Validation(_))));
+	}
+
+	#[test]
+	fn ranks_must_be_positive_numbers() {
+		for rank in ["0", "-1", "", "first", "1.5"] {
+			let error = order(&["A", "B"], &["1", rank]).unwrap_err();
+			assert_eq!(error, FriendVoteError::Validation(format!("Illegal rank for B: {rank}")));
+		}
+	}
+}
@@ -0,0 +1,700 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use nanoid::nanoid;
+use rand::seq::SliceRandom;
+use tokio::sync::broadcast;
+
+use crate::ballot::Ballot;
+use crate::endpoints::{
+	AdminInfo, AuditLogEntry, BallotFrequency, ElectionLinks, ElectionResults, MemberVoterStatus,
+	Presentation, QrLink, VoteCount, VotingInfo,
+};
+use crate::errors::FriendVoteError;
+use crate::qr::qr_code_svg;
+use crate::rate_limit::RateLimiter;
+use crate::store::{ElectionStore, ElectionTokens, NewElectionRecord, PairwiseCounts, VoteSummary};
+
+pub struct NewElection {
+	pub election_id: String,
+	pub admin_id: String,
+	pub result_id: String,
+}
+
+pub fn admin_path(election_id: &str, admin_id: &str) -> String {
+	format!("{}/admin/{}", election_id, admin_id)
+}
+
+/// The admin page of an election, for its owner while logged in.
+pub fn owner_admin_path(election_id: &str) -> String {
+	format!("{}/admin", election_id)
+}
+
+pub fn vote_path(election_id: &str) -> String {
+	format!("{}/vote", election_id)
+}
+
+pub fn result_path(election_id: &str, result_id: &str) -> String {
+	format!("{}/result/{}", election_id, result_id)
+}
+
+pub async fn create_election(
+	store: &dyn ElectionStore,
+	election_name: &String,
+	candidates: Vec<String>,
+	shuffle_candidates: bool,
+	review_ballots: bool,
+	owner_account_id: Option<String>,
+	members_only: bool,
+) -> Result<NewElection, FriendVoteError> {
+	let mut cleaned_candidates = candidates;
+	if cleaned_candidates.last().map(String::is_empty).unwrap_or(false) {
+		cleaned_candidates.pop();
+	}
+	if cleaned_candidates.len() <= 1 {
+		return Err(FriendVoteError::Validation("Not enough candidates.".to_string()));
+	}
+
+	let election_id = nanoid!(16);
+	let admin_id = nanoid!(16);
+	let result_id = nanoid!(16);
+
+	store
+		.insert_election(NewElectionRecord {
+			election_id: election_id.clone(),
+			name: election_name.clone(),
+			candidates: cleaned_candidates,
+			shuffle_candidates,
+			review_ballots,
+			tokens: ElectionTokens {
+				admin_id: admin_id.clone(),
+				result_id: result_id.clone(),
+			},
+			owner_account_id,
+			members_only,
+		})
+		.await?;
+	store.insert_audit_entry(&election_id, "Election created").await?;
+	println!("Successfully created election with name '{}'", election_name);
+
+	Ok(NewElection {
+		election_id,
+		admin_id,
+		result_id,
+	})
+}
+
+pub async fn admin_info(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	admin_id: &str,
+) -> Result<AdminInfo, FriendVoteError> {
+	let tokens = check_admin_token(store, election_id, admin_id).await?;
+	let election = store.get_election(election_id).await?;
+	let audit_log = store.get_audit_entries(election_id).await?;
+	let vote_summary = store.get_vote_summary(election_id).await?;
+
+	Ok(AdminInfo {
+		election_name: election.name,
+		admin_path: admin_path(election_id, &tokens.admin_id),
+		result_path: result_path(election_id, &tokens.result_id),
+		admin_id: tokens.admin_id,
+		vote_path: vote_path(election_id),
+		voting_open: election.voting_open,
+		vote_count: vote_count(&vote_summary),
+		join_code: election.join_code,
+		audit_log: audit_log
+			.into_iter()
+			.map(|entry| AuditLogEntry {
+				time: format_time(&entry.created_at),
+				action: entry.action,
+			})
+			.collect(),
+	})
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+	time.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+pub fn vote_count(summary: &VoteSummary) -> VoteCount {
+	VoteCount {
+		n_votes: summary.n_votes,
+		last_vote_at: summary.last_vote_at.as_ref().map(format_time),
+	}
+}
+
+/// Checks the admin token, then subscribes to the votes of the election.
+/// Returns the current count, and a receiver for the counts after it.
+pub async fn subscribe_vote_counts(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	admin_id: &str,
+) -> Result<(VoteSummary, broadcast::Receiver<VoteSummary>), FriendVoteError> {
+	check_admin_token(store, election_id, admin_id).await?;
+	subscribe_votes(store, election_id).await
+}
+
+/// Like [`subscribe_vote_counts`], but checks the results token, so results pages can follow the votes.
+pub async fn subscribe_result_vote_counts(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	result_id: &str,
+) -> Result<(VoteSummary, broadcast::Receiver<VoteSummary>), FriendVoteError> {
+	check_result_token(store, election_id, result_id).await?;
+	subscribe_votes(store, election_id).await
+}
+
+async fn subscribe_votes(
+	store: &dyn ElectionStore,
+	election_id: &str,
+) -> Result<(VoteSummary, broadcast::Receiver<VoteSummary>), FriendVoteError> {
+	// subscribe first, so no vote is missed between reading the count and subscribing
+	let receiver = store.subscribe_votes();
+	let summary = store.get_vote_summary(election_id).await?;
+	Ok((summary, receiver))
+}
+
+/// Join codes avoid characters that are easily confused, like 0 and O, or 1 and I.
+const JOIN_CODE_ALPHABET: [char; 31] = [
+	'2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'M', 'N', 'P',
+	'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+];
+const JOIN_CODE_LENGTH: usize = 6;
+/// With almost 900 million codes, a collision is rare, let alone many in a row.
+const JOIN_CODE_ATTEMPTS: usize = 10;
+
+/// Gives the open election a join code, after checking the admin token. An existing code is kept.
+pub async fn create_join_code(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	admin_id: &str,
+) -> Result<String, FriendVoteError> {
+	check_admin_token(store, election_id, admin_id).await?;
+	let election = store.get_election(election_id).await?;
+	if !election.voting_open {
+		return Err(FriendVoteError::Closed("Voting is closed.".to_string()));
+	}
+	if let Some(join_code) = election.join_code {
+		return Ok(join_code);
+	}
+	for _ in 0..JOIN_CODE_ATTEMPTS {
+		let join_code = nanoid!(JOIN_CODE_LENGTH, &JOIN_CODE_ALPHABET);
+		if store.set_join_code(election_id, &join_code).await? {
+			store.insert_audit_entry(election_id, "Join code created").await?;
+			return Ok(join_code);
+		}
+	}
+	Err(FriendVoteError::Internal("Could not find a free join code.".to_string()))
+}
+
+/// Finds the election with the join code, as typed by a voter. Every attempt of `client` counts,
+/// found or not, so that codes cannot be guessed by trying them all.
+pub async fn resolve_join_code(
+	store: &dyn ElectionStore,
+	limiter: &RateLimiter,
+	client: IpAddr,
+	join_code: &str,
+) -> Result<String, FriendVoteError> {
+	limiter.check(client)?;
+	// be lenient with how the code was typed, e.g. "abc 234"
+	let join_code = join_code
+		.chars()
+		.filter(|character| character.is_ascii_alphanumeric())
+		.map(|character| character.to_ascii_uppercase())
+		.collect::<String>();
+	if join_code.len() != JOIN_CODE_LENGTH {
+		return Err(FriendVoteError::Validation(format!("Join codes have {} characters.", JOIN_CODE_LENGTH)));
+	}
+	store
+		.find_election_by_join_code(&join_code)
+		.await?
+		.ok_or_else(|| FriendVoteError::NotFound("No open election has this join code.".to_string()))
+}
+
+/// Opens or closes voting, after checking the admin token.
+pub async fn update_voting_open(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	admin_id: &str,
+	voting_open: bool,
+) -> Result<(), FriendVoteError> {
+	check_admin_token(store, election_id, admin_id).await?;
+	store.set_voting_open(election_id, voting_open).await?;
+	store
+		.insert_audit_entry(
+			election_id,
+			if voting_open {
+				"Voting reopened"
+			} else {
+				"Voting closed"
+			},
+		)
+		.await?;
+	Ok(())
+}
+
+async fn check_admin_token(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	admin_id: &str,
+) -> Result<ElectionTokens, FriendVoteError> {
+	let tokens = store.get_tokens(election_id).await?;
+	if *admin_id != tokens.admin_id {
+		return Err(FriendVoteError::Forbidden("Wrong admin link.".to_string()));
+	}
+	Ok(tokens)
+}
+
+/// Returns the admin token of an election, if the account owns it. Owners need no admin link.
+pub async fn owner_admin_id(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	account_id: &str,
+) -> Result<String, FriendVoteError> {
+	let election = store.get_election(election_id).await?;
+	if election.owner_account_id.as_deref() != Some(account_id) {
+		return Err(FriendVoteError::Forbidden("This election belongs to another account.".to_string()));
+	}
+	Ok(store.get_tokens(election_id).await?.admin_id)
+}
+
+async fn check_result_token(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	result_id: &str,
+) -> Result<ElectionTokens, FriendVoteError> {
+	let tokens = store.get_tokens(election_id).await?;
+	if *result_id != tokens.result_id {
+		return Err(FriendVoteError::Forbidden("Wrong results link.".to_string()));
+	}
+	Ok(tokens)
+}
+
+pub async fn vote_info(store: &dyn ElectionStore, election_id: &str) -> Result<VotingInfo, FriendVoteError> {
+	let mut db_entry = store.get_election(election_id).await?;
+	// shuffled anew for every request, so that the order a voter saw is not recorded anywhere
+	if db_entry.shuffle_candidates {
+		db_entry.candidates.shuffle(&mut rand::thread_rng());
+	}
+
+	Ok(VotingInfo {
+		election_name: db_entry.name,
+		candidates: db_entry.candidates,
+		voting_open: db_entry.voting_open,
+		review_ballots: db_entry.review_ballots,
+		members_only: db_entry.members_only,
+	})
+}
+
+/// Whether the account, if any, can vote in a members-only election.
+pub async fn member_voter_status(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	account_id: Option<&str>,
+) -> Result<MemberVoterStatus, FriendVoteError> {
+	let Some(account_id) = account_id else {
+		return Ok(MemberVoterStatus::LoggedOut);
+	};
+	if !store.get_account(account_id).await?.oidc_member {
+		return Ok(MemberVoterStatus::NotMember);
+	}
+	if store.has_voted(election_id, account_id).await? {
+		return Ok(MemberVoterStatus::Voted);
+	}
+	Ok(MemberVoterStatus::CanVote)
+}
+
+/// Stores a ballot given as the ordered candidate names and, for each pair of consecutive candidates,
+/// whether the first is strictly preferred over the second (`false` meaning they are tied).
+/// In members-only elections, `voter` is the logged in account, which must be in the voter group
+/// and can vote once. In other elections it is ignored, so that nothing records who voted.
+pub async fn cast_vote(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	candidates: Vec<String>,
+	comparator_is_bigger: Vec<bool>,
+	voter: Option<&str>,
+) -> Result<(), FriendVoteError> {
+	let db_entry = store.get_election(election_id).await?;
+
+	if !db_entry.voting_open {
+		return Err(FriendVoteError::Closed("Voting is closed.".to_string()));
+	}
+	let voter = if db_entry.members_only {
+		match member_voter_status(store, election_id, voter).await? {
+			MemberVoterStatus::LoggedOut => {
+				return Err(FriendVoteError::CannotVote("Log in to vote in this election.".to_string()));
+			},
+			MemberVoterStatus::NotMember => {
+				return Err(FriendVoteError::CannotVote(
+					"Only members of the voter group can vote in this election.".to_string(),
+				));
+			},
+			// checked again when the ballot is stored, in case of two ballots at once
+			MemberVoterStatus::Voted | MemberVoterStatus::CanVote => voter,
+		}
+	} else {
+		None
+	};
+
+	// get sorted indices
+	if candidates.len() != db_entry.candidates.len() {
+		return Err(FriendVoteError::Validation(format!(
+			"Number of candidates does not match: {}",
+			candidates.len()
+		)));
+	}
+	if comparator_is_bigger.len() + 1 != candidates.len() {
+		return Err(FriendVoteError::Validation(format!(
+			"Number of comparisons does not match: {}",
+			comparator_is_bigger.len()
+		)));
+	}
+	let mut candidate_order = Vec::with_capacity(candidates.len());
+	let mut found;
+	for candidate in candidates {
+		found = false;
+		for (ind, orig_candidate) in db_entry.candidates.iter().enumerate() {
+			if candidate == *orig_candidate {
+				if candidate_order.contains(&ind) {
+					return Err(FriendVoteError::Validation(format!("Duplicate candidate: {candidate}")));
+				}
+				candidate_order.push(ind);
+				found = true;
+				break;
+			}
+		}
+		if !found {
+			return Err(FriendVoteError::Validation(format!("Illegal candidate: {candidate}")));
+		}
+	}
+
+	// insert the vote
+	store
+		.insert_ballot(election_id, &Ballot::from_order(&candidate_order, &comparator_is_bigger), voter)
+		.await?;
+	Ok(())
+}
+
+/// Identifies the tally method and its settings, so results cached for another configuration are not reused.
+const TALLY_CONFIGURATION: &str = "ranked_pairs";
+
+pub async fn election_results(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	result_id: &str,
+) -> Result<ElectionResults, FriendVoteError> {
+	use crate::ranked_pairs::calculate_ranks;
+
+	check_result_token(store, election_id, result_id).await?;
+	// the version is read before the tally, so cached results are never older than their version
+	let db_entry = store.get_election(election_id).await?;
+	if let Some(results) = store
+		.get_cached_results(election_id, TALLY_CONFIGURATION, db_entry.results_version)
+		.await?
+	{
+		return Ok(results);
+	}
+
+	// fetch the tally, which is kept up to date with every vote
+	let PairwiseCounts {
+		n_votes,
+		defeats_matrix,
+	} = store.get_pairwise_counts(election_id).await?;
+	let ballot_frequencies = store
+		.get_ballot_patterns(election_id)
+		.await?
+		.into_iter()
+		.map(|weighted| BallotFrequency {
+			ranking: weighted
+				.ballot
+				.groups()
+				.iter()
+				.map(|group| {
+					group
+						.iter()
+						.map(|cand_ind| db_entry.candidates[*cand_ind].clone())
+						.collect()
+				})
+				.collect(),
+			count: weighted.weight,
+		})
+		.collect();
+
+	// calculate ranks
+	let mut ranked_candidates = Vec::new();
+	{
+		let cand_ranks = calculate_ranks(&defeats_matrix);
+		let max_rank = cand_ranks.iter().copied().max().unwrap_or(0);
+		for _ in 0..(max_rank + 1) {
+			ranked_candidates.push(Vec::new());
+		}
+		for (cand_ind, rank) in cand_ranks.iter().enumerate() {
+			ranked_candidates[*rank].push(db_entry.candidates[cand_ind].clone());
+		}
+	}
+
+	let results = ElectionResults {
+		election_name: db_entry.name,
+		candidates: db_entry.candidates,
+		n_votes,
+		defeats_matrix,
+		ranked_candidates,
+		ballot_frequencies,
+	};
+	// a failure to cache only costs a recomputation on the next visit
+	if let Err(error) = store
+		.cache_results(election_id, TALLY_CONFIGURATION, db_entry.results_version, &results)
+		.await
+	{
+		tracing::warn!("could not cache results of election {}: {}", election_id, error);
+	}
+	Ok(results)
+}
+
+/// The results for the presentation page, with a QR code of the vote link while voting is open.
+/// `origin` is the scheme and host the page was requested on, as QR codes need absolute links.
+pub async fn presentation(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	result_id: &str,
+	origin: &str,
+) -> Result<Presentation, FriendVoteError> {
+	let results = election_results(store, election_id, result_id).await?;
+	let vote_link = if store.get_election(election_id).await?.voting_open {
+		Some(qr_link(origin, &vote_path(election_id))?)
+	} else {
+		None
+	};
+	Ok(Presentation { results, vote_link })
+}
+
+/// The absolute admin, vote and results links with their QR codes, after checking the admin token.
+pub async fn election_links(
+	store: &dyn ElectionStore,
+	election_id: &str,
+	admin_id: &str,
+	origin: &str,
+) -> Result<ElectionLinks, FriendVoteError> {
+	let tokens = check_admin_token(store, election_id, admin_id).await?;
+	Ok(ElectionLinks {
+		admin: qr_link(origin, &admin_path(election_id, &tokens.admin_id))?,
+		vote: qr_link(origin, &vote_path(election_id))?,
+		result: qr_link(origin, &result_path(election_id, &tokens.result_id))?,
+	})
+}
+
+fn qr_link(origin: &str, path: &str) -> Result<QrLink, FriendVoteError> {
+	let url = format!("{}/{}", origin, path);
+	Ok(QrLink {
+		qr_code: qr_code_svg(&url)?,
+		url,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::{IpAddr, Ipv4Addr};
+	use std::time::Duration;
+
+	use super::*;
+	use crate::store::{Account, MemoryStore};
+
+	fn names(names: &[&str]) -> Vec<String> {
+		names.iter().map(|name| name.to_string()).collect()
+	}
+
+	async fn new_election(store: &dyn ElectionStore, members_only: bool) -> NewElection {
+		create_election(store, &"Lunch".to_string(), names(&["A", "B", "C"]), false, false, None, members_only)
+			.await
+			.unwrap()
+	}
+
+	#[tokio::test]
+	async fn votes_are_counted_in_the_results() {
+		let store = MemoryStore::new();
+		let election = new_election(&store, false).await;
+
+		cast_vote(&store, &election.election_id, names(&["A", "B", "C"]), vec![true, true], None)
+			.await
+			.unwrap();
+		cast_vote(&store, &election.election_id, names(&["B", "A", "C"]), vec![true, true], None)
+			.await
+			.unwrap();
+		cast_vote(&store, &election.election_id, names(&["A", "C", "B"]), vec![true, false], None)
+			.await
+			.unwrap();
+
+		let results = election_results(&store, &election.election_id, &election.result_id)
+			.await
+			.unwrap();
+		assert_eq!(results.n_votes, 3);
+		assert_eq!(results.ranked_candidates, vec![names(&["A"]), names(&["B"]), names(&["C"])]);
+		// A beats B on two ballots, and B ties C on one
+		assert_eq!(results.defeats_matrix[0][1], Some(2));
+		assert_eq!(results.defeats_matrix[1][2], Some(2));
+		assert_eq!(results.defeats_matrix[2][1], Some(0));
+	}
+
+	#[tokio::test]
+	async fn results_without_votes_rank_everyone_first() {
+		let store = MemoryStore::new();
+		let election = new_election(&store, false).await;
+
+		let results = election_results(&store, &election.election_id, &election.result_id)
+			.await
+			.unwrap();
+		assert_eq!(results.n_votes, 0);
+		assert_eq!(results.ranked_candidates, vec![names(&["A", "B", "C"])]);
+	}
+
+	#[tokio::test]
+	async fn results_need_the_result_token() {
+		let store = MemoryStore::new();
+		let election = new_election(&store, false).await;
+
+		let error = election_results(&store, &election.election_id, &election.admin_id)
+			.await
+			.unwrap_err();
+		assert!(matches!(error, FriendVoteError::Forbidden(_)));
+	}
+
+	#[tokio::test]
+	async fn invalid_ballots_are_refused() {
+		let store = MemoryStore::new();
+		let election = new_election(&store, false).await;
+
+		for (candidates, comparator_is_bigger) in [
+			(names(&["A", "B"]), vec![true]),
+			(names(&["A", "A", "C"]), vec![true, true]),
+			(names(&["A", "B", "D"]), vec![true, true]),
+			(names(&["A", "B", "C"]), vec![true]),
+		] {
+			let error = cast_vote(&store, &election.election_id, candidates, comparator_is_bigger, None)
+				.await
+				.unwrap_err();
+			assert!(matches!(error, FriendVoteError::Validation(_)));
+		}
+		assert_eq!(
+			store
+				.get_pairwise_counts(&election.election_id)
+				.await
+				.unwrap()
+				.n_votes,
+			0
+		);
+	}
+
+	#[tokio::test]
+	async fn closed_elections_take_no_votes() {
+		let store = MemoryStore::new();
+		let election = new_election(&store, false).await;
+		update_voting_open(&store, &election.election_id, &election.admin_id, false)
+			.await
+			.unwrap();
+
+		let error = cast_vote(&store, &election.election_id, names(&["A", "B", "C"]), vec![true, true], None)
+			.await
+			.unwrap_err();
+		assert!(matches!(error, FriendVoteError::Closed(_)));
+	}
+
+	#[tokio::test]
+	async fn join_codes_find_open_elections() {
+		let store = MemoryStore::new();
+		let limiter = RateLimiter::new(10, Duration::from_secs(60));
+		let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+		let election = new_election(&store, false).await;
+
+		let join_code = create_join_code(&store, &election.election_id, &election.admin_id)
+			.await
+			.unwrap();
+		assert_eq!(join_code.len(), JOIN_CODE_LENGTH);
+		assert_eq!(
+			create_join_code(&store, &election.election_id, &election.admin_id)
+				.await
+				.unwrap(),
+			join_code
+		);
+		// typed in lower case and in two halves
+		let typed = format!("{} {}", &join_code[..3], &join_code[3..]).to_lowercase();
+		assert_eq!(resolve_join_code(&store, &limiter, client, &typed).await.unwrap(), election.election_id);
+
+		// the code is freed when voting closes
+		update_voting_open(&store, &election.election_id, &election.admin_id, false)
+			.await
+			.unwrap();
+		let error = resolve_join_code(&store, &limiter, client, &join_code)
+			.await
+			.unwrap_err();
+		assert!(matches!(error, FriendVoteError::NotFound(_)));
+	}
+
+	#[tokio::test]
+	async fn join_code_lookups_are_rate_limited() {
+		let store = MemoryStore::new();
+		let limiter = RateLimiter::new(2, Duration::from_secs(60));
+		let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+		for _ in 0..2 {
+			let error = resolve_join_code(&store, &limiter, client, "ABC234")
+				.await
+				.unwrap_err();
+			assert!(matches!(error, FriendVoteError::NotFound(_)));
+		}
+		let error = resolve_join_code(&store, &limiter, client, "ABC234")
+			.await
+			.unwrap_err();
+		assert!(matches!(error, FriendVoteError::TooManyRequests(_)));
+	}
+
+	#[tokio::test]
+	async fn members_vote_once_in_members_only_elections() {
+		let store = MemoryStore::new();
+		let election = new_election(&store, true).await;
+		for (account_id, oidc_member) in [("member", true), ("outsider", false)] {
+			store
+				.insert_account(Account {
+					account_id: account_id.to_string(),
+					username: account_id.to_string(),
+					password_hash: None,
+					oidc_subject: Some(account_id.to_string()),
+					oidc_member,
+				})
+				.await
+				.unwrap();
+		}
+		let vote =
+			|voter| cast_vote(&store, &election.election_id, names(&["A", "B", "C"]), vec![true, true], voter);
+
+		assert!(matches!(vote(None).await.unwrap_err(), FriendVoteError::CannotVote(_)));
+		assert!(matches!(vote(Some("outsider")).await.unwrap_err(), FriendVoteError::CannotVote(_)));
+		vote(Some("member")).await.unwrap();
+		assert!(matches!(
+			member_voter_status(&store, &election.election_id, Some("member"))
+				.await
+				.unwrap(),
+			MemberVoterStatus::Voted
+		));
+		assert!(matches!(vote(Some("member")).await.unwrap_err(), FriendVoteError::CannotVote(_)));
+		assert_eq!(
+			store
+				.get_pairwise_counts(&election.election_id)
+				.await
+				.unwrap()
+				.n_votes,
+			1
+		);
+	}
+}
@@ -10,124 +10,266 @@
 /// If not, see <https://www.gnu.org/licenses/>.
 use leptos::prelude::{server, ServerFnError};
 
+use crate::errors::FriendVoteError;
+
 #[server]
-pub async fn create_election(election_name: String, candidates: Vec<String>) -> Result<(), ServerFnError> {
-	let mut cleaned_candidates = candidates.clone();
-	if cleaned_candidates.last().map(String::is_empty).unwrap_or(false) {
-		cleaned_candidates.pop();
-	}
-	if cleaned_candidates.len() > 1 {
-		// store the election
-		use crate::db::insert_new_election;
-		use leptos::prelude::use_context;
-		use nanoid::nanoid;
-		use sea_orm::DatabaseConnection;
-
-		let db_conn = match use_context::<DatabaseConnection>() {
-			Some(p) => p,
-			None => {
-				return Err(ServerFnError::ServerError(
-					"Could not find db connection in context.".to_string(),
-				));
-			},
-		};
+pub async fn create_election(
+	election_name: String,
+	candidates: Vec<String>,
+	#[server(default)] shuffle_candidates: bool,
+	#[server(default)] review_ballots: bool,
+	#[server(default)] members_only: bool,
+) -> Result<(), ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
+	use leptos::prelude::use_context;
 
-		let election_id = nanoid!(16);
-		let admin_id = nanoid!(16);
-		let result_id = nanoid!(16);
-
-		insert_new_election(
-			&db_conn,
-			&election_name,
-			&cleaned_candidates,
-			&election_id,
-			&admin_id,
-			&result_id,
+	let store = match use_context::<Store>() {
+		Some(p) => p,
+		None => {
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+	// nobody could vote in a members-only election without a provider to log in through
+	if members_only && use_context::<crate::oidc::Oidc>().is_none() {
+		return Err(FriendVoteError::Validation(
+			"Members-only elections need OpenID Connect to be configured.".to_string(),
 		)
-		.await?;
-		println!("Successfully created election with name '{}'", election_name);
-
-		// and redirect to the admin page
-		leptos_axum::redirect(format!("{}/admin/{}", election_id, admin_id).as_str());
-		Ok(())
-	} else {
-		Err(ServerFnError::ServerError("Not enough candidates.".to_string()))
+		.into_server_fn_error());
 	}
+
+	// elections created while logged in belong to the account
+	let owner_account_id = crate::accounts::session_account_id(store.as_ref(), &request_headers())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+
+	// store the election
+	let new_election = crate::elections::create_election(
+		store.as_ref(),
+		&election_name,
+		candidates,
+		shuffle_candidates,
+		review_ballots,
+		owner_account_id,
+		members_only,
+	)
+	.await
+	.map_err(FriendVoteError::into_server_fn_error)?;
+
+	// and redirect to the admin page
+	leptos_axum::redirect(
+		crate::elections::admin_path(&new_election.election_id, &new_election.admin_id).as_str(),
+	);
+	Ok(())
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
 pub struct AdminInfo {
 	pub election_name: String,
+	/// The admin token, also when the page was opened by the owner of the election without it.
+	pub admin_id: String,
 	pub admin_path: String,
 	pub vote_path: String,
 	pub result_path: String,
+	pub voting_open: bool,
+	pub vote_count: VoteCount,
+	/// Short code that voters can type on the home page, while voting is open.
+	pub join_code: Option<String>,
+	pub audit_log: Vec<AuditLogEntry>,
+}
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct AuditLogEntry {
+	pub time: String,
+	pub action: String,
+}
+
+/// The number of ballots cast so far, without anything about their contents.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct VoteCount {
+	pub n_votes: u64,
+	/// When the latest ballot was cast, if any.
+	pub last_vote_at: Option<String>,
 }
 #[server]
 pub async fn get_election_admin_info(
 	election_id: String,
 	admin_id: String,
-) -> Result<AdminInfo, ServerFnError> {
-	use crate::db::get_election_details;
+) -> Result<AdminInfo, ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
 	use leptos::prelude::use_context;
-	use sea_orm::DatabaseConnection;
 
-	let db_conn = match use_context::<DatabaseConnection>() {
+	let store = match use_context::<Store>() {
 		Some(p) => p,
 		None => {
-			return Err(ServerFnError::ServerError("Could not find db connection in context.".to_string()));
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
 		},
 	};
 
-	let db_entry = get_election_details(&db_conn, &election_id).await?;
+	let admin_id = resolve_admin_id(store.as_ref(), &election_id, admin_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	crate::elections::admin_info(store.as_ref(), &election_id, &admin_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+#[server]
+pub async fn set_voting_open(
+	election_id: String,
+	admin_id: String,
+	voting_open: bool,
+) -> Result<(), ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
+	use leptos::prelude::use_context;
 
-	if admin_id == db_entry.admin_id {
-		Ok(AdminInfo {
-			election_name: db_entry.name,
-			admin_path: format!("{}/admin/{}", db_entry.election_id, db_entry.admin_id),
-			vote_path: format!("{}/vote", db_entry.election_id),
-			result_path: format!("{}/result/{}", db_entry.election_id, db_entry.result_id),
-		})
-	} else {
-		Err(ServerFnError::new("forbidden"))
-	}
+	let store = match use_context::<Store>() {
+		Some(p) => p,
+		None => {
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	let admin_id = resolve_admin_id(store.as_ref(), &election_id, admin_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	crate::elections::update_voting_open(store.as_ref(), &election_id, &admin_id, voting_open)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+#[server]
+pub async fn create_join_code(
+	election_id: String,
+	admin_id: String,
+) -> Result<String, ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let store = match use_context::<Store>() {
+		Some(p) => p,
+		None => {
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	let admin_id = resolve_admin_id(store.as_ref(), &election_id, admin_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	crate::elections::create_join_code(store.as_ref(), &election_id, &admin_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+#[server]
+pub async fn join_election(join_code: String) -> Result<(), ServerFnError<FriendVoteError>> {
+	use crate::rate_limit::JoinCodeLimiter;
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let (store, limiter) = match (use_context::<Store>(), use_context::<JoinCodeLimiter>()) {
+		(Some(store), Some(limiter)) => (store, limiter),
+		_ => {
+			return Err(FriendVoteError::Internal(
+				"Could not find store or rate limiter in context.".to_string(),
+			)
+			.into_server_fn_error());
+		},
+	};
+	let client = client_address()
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+
+	let election_id = crate::elections::resolve_join_code(store.as_ref(), &limiter, client, &join_code)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+
+	// and redirect to the vote page
+	leptos_axum::redirect(&format!("/{}", crate::elections::vote_path(&election_id)));
+	Ok(())
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
 pub struct VotingInfo {
 	pub election_name: String,
 	pub candidates: Vec<String>,
+	pub voting_open: bool,
+	pub review_ballots: bool,
+	/// Whether only logged in members of the OpenID Connect voter group can vote, once each.
+	pub members_only: bool,
 }
 #[server]
-pub async fn get_election_vote_info(election_id: String) -> Result<VotingInfo, ServerFnError> {
-	use crate::db::get_election_details;
+pub async fn get_election_vote_info(
+	election_id: String,
+) -> Result<VotingInfo, ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
 	use leptos::prelude::use_context;
-	use sea_orm::DatabaseConnection;
 
-	let db_conn = match use_context::<DatabaseConnection>() {
+	let store = match use_context::<Store>() {
 		Some(p) => p,
 		None => {
-			return Err(ServerFnError::ServerError("Could not find db connection in context.".to_string()));
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
 		},
 	};
 
-	let db_entry = get_election_details(&db_conn, &election_id).await?;
+	crate::elections::vote_info(store.as_ref(), &election_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+/// Whether the visitor can vote in a members-only election.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberVoterStatus {
+	LoggedOut,
+	/// Logged in, but not as a member of the voter group.
+	NotMember,
+	CanVote,
+	Voted,
+}
+#[server]
+pub async fn get_member_voter_status(
+	election_id: String,
+) -> Result<MemberVoterStatus, ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let store = match use_context::<Store>() {
+		Some(p) => p,
+		None => {
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
 
-	Ok(VotingInfo {
-		election_name: db_entry.name,
-		candidates: db_entry.candidates,
-	})
+	let account_id = crate::accounts::session_account_id(store.as_ref(), &request_headers())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	crate::elections::member_voter_status(store.as_ref(), &election_id, account_id.as_deref())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
 }
 
 #[server]
 pub async fn cast_vote(
 	election_id: String,
 	candidates: Vec<String>,
-	comp: Vec<String>,
-) -> Result<(), ServerFnError> {
-	use crate::db::{get_election_details, insert_vote};
+	#[server(default)] comp: Vec<String>,
+	// the ballot for browsers without JavaScript gives a rank to each candidate instead of `comp`
+	#[server(default)] ranks: Vec<String>,
+) -> Result<(), ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
 	use leptos::prelude::use_context;
-	use leptos::server_fn::error::NoCustomError;
-	use sea_orm::DatabaseConnection;
+
+	if candidates.is_empty() {
+		return Err(FriendVoteError::Validation("No candidates given.".to_string()).into_server_fn_error());
+	}
 
 	// calculate comparison states from checkbox ids
 	let mut comparator_is_bigger = Vec::with_capacity(candidates.len() - 1);
@@ -137,10 +279,11 @@ pub async fn cast_vote(
 		comp_ind = comp_name
 			.split("_")
 			.last()
-			.ok_or(ServerFnError::<NoCustomError>::ServerError(
-				"Could not find index in comp name.".to_string(),
-			))?
-			.parse::<usize>()?;
+			.and_then(|ind| ind.parse::<usize>().ok())
+			.ok_or_else(|| {
+				FriendVoteError::Validation("Could not find index in comp name.".to_string())
+					.into_server_fn_error()
+			})?;
 		if comp_ind >= (candidates.len() - 1) {
 			break;
 		}
@@ -153,128 +296,473 @@ pub async fn cast_vote(
 	for _ind in curr_ind..(candidates.len() - 1) {
 		comparator_is_bigger.push(false);
 	}
+	let (candidates, comparator_is_bigger) = if ranks.is_empty() {
+		(candidates, comparator_is_bigger)
+	} else {
+		crate::components::rank_select::order_from_ranks(candidates, &ranks)
+			.map_err(FriendVoteError::into_server_fn_error)?
+	};
 
-	// get election info
-	let db_conn = match use_context::<DatabaseConnection>() {
+	// insert the vote
+	let store = match use_context::<Store>() {
 		Some(p) => p,
 		None => {
-			return Err(ServerFnError::ServerError("Could not find db connection in context.".to_string()));
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
 		},
 	};
-	let db_entry = get_election_details(&db_conn, &election_id).await?;
-
-	// get sorted indices
-	if candidates.len() != db_entry.candidates.len() {
-		return Err(ServerFnError::ServerError(format!(
-			"Number of candidates does not match: {}",
-			candidates.len()
-		)));
-	}
-	let mut candidate_order = Vec::with_capacity(candidates.len());
-	let mut found;
-	for candidate in candidates {
-		found = false;
-		for (ind, orig_candidate) in db_entry.candidates.iter().enumerate() {
-			if candidate == *orig_candidate {
-				candidate_order.push(ind as i32);
-				found = true;
-				break;
-			}
-		}
-		if !found {
-			return Err(ServerFnError::ServerError(format!("Illegal candidate: {candidate}")));
-		}
-	}
-
-	// insert the vote
-	insert_vote(&db_conn, &election_id, &candidate_order, &comparator_is_bigger).await?;
+	let voter = crate::accounts::session_account_id(store.as_ref(), &request_headers())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	crate::elections::cast_vote(
+		store.as_ref(),
+		&election_id,
+		candidates,
+		comparator_is_bigger,
+		voter.as_deref(),
+	)
+	.await
+	.map_err(FriendVoteError::into_server_fn_error)?;
 
-	// and redirect to the vote thanks page
-	leptos_axum::redirect("vote_thanks");
+	// and redirect to the vote thanks page, from the root as forms without JavaScript post to the api path
+	leptos_axum::redirect("/vote_thanks");
 	Ok(())
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
 pub struct ElectionResults {
 	pub election_name: String,
 	pub candidates: Vec<String>,
 	pub n_votes: u64,
 	pub defeats_matrix: Vec<Vec<Option<u64>>>,
 	pub ranked_candidates: Vec<Vec<String>>,
+	/// The distinct ballots, most frequent first.
+	pub ballot_frequencies: Vec<BallotFrequency>,
+}
+
+/// A ranking cast by `count` voters, as groups of tied candidates from most to least preferred.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct BallotFrequency {
+	pub ranking: Vec<Vec<String>>,
+	pub count: u64,
 }
 #[server]
 pub async fn get_election_results(
 	election_id: String,
 	result_id: String,
-) -> Result<ElectionResults, ServerFnError> {
-	use crate::db::get_election_details;
-	use crate::entities::prelude::Votes;
-	use crate::entities::votes;
-	use crate::ranked_pairs::calculate_ranks;
+) -> Result<ElectionResults, ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
 	use leptos::prelude::use_context;
-	use sea_orm::{ColumnTrait as _, DatabaseConnection, EntityTrait as _, QueryFilter as _};
 
-	let db_conn = match use_context::<DatabaseConnection>() {
+	let store = match use_context::<Store>() {
 		Some(p) => p,
 		None => {
-			return Err(ServerFnError::ServerError("Could not find db connection in context.".to_string()));
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
 		},
 	};
 
-	let db_entry = get_election_details(&db_conn, &election_id).await?;
+	crate::elections::election_results(store.as_ref(), &election_id, &result_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
 
-	if result_id != db_entry.result_id {
-		return Err(ServerFnError::new("forbidden"));
-	}
+/// A link with its QR code, as an inline SVG image.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct QrLink {
+	pub url: String,
+	pub qr_code: String,
+}
 
-	// fetch votes
-	let votes = Votes::find()
-		.filter(votes::Column::ElectionId.eq(election_id))
-		.all(&db_conn)
-		.await?;
-
-	// calculate defeats matrix
-	let mut defeats_matrix = Vec::with_capacity(db_entry.candidates.len());
-	{
-		for i in 0..db_entry.candidates.len() {
-			defeats_matrix.push(vec![Some(0u64); db_entry.candidates.len()]);
-			defeats_matrix[i][i] = None;
-		}
-		let mut other_ind;
-		let mut bigger;
-		for vote in votes.iter() {
-			for (i, ind) in vote.cand_order.iter().enumerate() {
-				bigger = false;
-				for j in (i + 1)..vote.cand_order.len() {
-					other_ind = vote.cand_order[j];
-					bigger = bigger || vote.comp_is_bigger[j - 1];
-					if bigger {
-						defeats_matrix[*ind as usize][other_ind as usize] =
-							defeats_matrix[*ind as usize][other_ind as usize].map(|x| x + 1u64);
-					}
-				}
-			}
-		}
-	}
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct Presentation {
+	pub results: ElectionResults,
+	/// Only while voting is open.
+	pub vote_link: Option<QrLink>,
+}
+#[server]
+pub async fn get_presentation(
+	election_id: String,
+	result_id: String,
+) -> Result<Presentation, ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
+	use leptos::prelude::use_context;
 
-	// calculate ranks
-	let mut ranked_candidates = Vec::new();
-	{
-		let cand_ranks = calculate_ranks(&defeats_matrix);
-		let max_rank = *cand_ranks.values().max().unwrap();
-		for _ in 0..(max_rank + 1) {
-			ranked_candidates.push(Vec::new());
-		}
-		for (cand_ind, rank) in cand_ranks.iter() {
-			ranked_candidates[*rank].push(db_entry.candidates[*cand_ind].clone());
-		}
+	let store = match use_context::<Store>() {
+		Some(p) => p,
+		None => {
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	crate::elections::presentation(store.as_ref(), &election_id, &result_id, &request_origin())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+/// The links of an election with their QR codes, for the admin page.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ElectionLinks {
+	pub admin: QrLink,
+	pub vote: QrLink,
+	pub result: QrLink,
+}
+#[server]
+pub async fn get_election_links(
+	election_id: String,
+	admin_id: String,
+) -> Result<ElectionLinks, ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let store = match use_context::<Store>() {
+		Some(p) => p,
+		None => {
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	let admin_id = resolve_admin_id(store.as_ref(), &election_id, admin_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	crate::elections::election_links(store.as_ref(), &election_id, &admin_id, &request_origin())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct AccountInfo {
+	pub username: String,
+	pub n_passkeys: usize,
+	pub elections: Vec<AccountElection>,
+}
+/// An election of the account, managed through the session rather than the admin link.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct AccountElection {
+	pub name: String,
+	pub admin_path: String,
+}
+/// Returns the logged in account, if any.
+#[server]
+pub async fn get_account_info() -> Result<Option<AccountInfo>, ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let store = match use_context::<Store>() {
+		Some(p) => p,
+		None => {
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	let Some(account_id) = crate::accounts::session_account_id(store.as_ref(), &request_headers())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?
+	else {
+		return Ok(None);
+	};
+	crate::accounts::account_info(store.as_ref(), &account_id)
+		.await
+		.map(Some)
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+/// Returns the name of the OpenID Connect provider to log in with, if one is configured.
+#[server]
+pub async fn get_oidc_provider_name() -> Result<Option<String>, ServerFnError<FriendVoteError>> {
+	use leptos::prelude::use_context;
+
+	Ok(use_context::<crate::oidc::Oidc>().map(|oidc| oidc.provider_name().to_string()))
+}
+
+#[server]
+pub async fn sign_up(username: String, password: String) -> Result<(), ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let store = match use_context::<Store>() {
+		Some(p) => p,
+		None => {
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	let account_id = crate::accounts::sign_up(store.as_ref(), &username, &password)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	log_in_session(store.as_ref(), &account_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+#[server]
+pub async fn log_in(username: String, password: String) -> Result<(), ServerFnError<FriendVoteError>> {
+	use crate::rate_limit::LoginLimiter;
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let (store, LoginLimiter(limiter)) = match (use_context::<Store>(), use_context::<LoginLimiter>()) {
+		(Some(store), Some(limiter)) => (store, limiter),
+		_ => {
+			return Err(FriendVoteError::Internal(
+				"Could not find store or rate limiter in context.".to_string(),
+			)
+			.into_server_fn_error());
+		},
+	};
+	let client = client_address()
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+
+	let account_id = crate::accounts::log_in(store.as_ref(), &limiter, client, &username, &password)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	log_in_session(store.as_ref(), &account_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+#[server]
+pub async fn log_out() -> Result<(), ServerFnError<FriendVoteError>> {
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let store = match use_context::<Store>() {
+		Some(p) => p,
+		None => {
+			return Err(FriendVoteError::Internal("Could not find store in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	let cookie = crate::accounts::end_session(store.as_ref(), &request_headers(), request_is_secure())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	set_cookie(&cookie).map_err(FriendVoteError::into_server_fn_error)?;
+	leptos_axum::redirect("/account");
+	Ok(())
+}
+
+/// A passkey ceremony that the browser continues with the authenticator.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct PasskeyChallenge<T> {
+	pub ceremony_id: String,
+	pub options: T,
+}
+/// Starts adding a passkey to the logged in account.
+#[server]
+pub async fn start_passkey_registration(
+) -> Result<PasskeyChallenge<webauthn_rs_proto::CreationChallengeResponse>, ServerFnError<FriendVoteError>> {
+	use crate::accounts::Passkeys;
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let (store, passkeys) = match (use_context::<Store>(), use_context::<Passkeys>()) {
+		(Some(store), Some(passkeys)) => (store, passkeys),
+		_ => {
+			return Err(FriendVoteError::Internal("Could not find store or passkeys in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	let account_id = logged_in_account_id(store.as_ref())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	let (ceremony_id, options) = passkeys
+		.start_registration(store.as_ref(), &account_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	Ok(PasskeyChallenge { ceremony_id, options })
+}
+
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn finish_passkey_registration(
+	ceremony_id: String,
+	credential: webauthn_rs_proto::RegisterPublicKeyCredential,
+) -> Result<(), ServerFnError<FriendVoteError>> {
+	use crate::accounts::Passkeys;
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let (store, passkeys) = match (use_context::<Store>(), use_context::<Passkeys>()) {
+		(Some(store), Some(passkeys)) => (store, passkeys),
+		_ => {
+			return Err(FriendVoteError::Internal("Could not find store or passkeys in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	let account_id = logged_in_account_id(store.as_ref())
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	passkeys
+		.finish_registration(store.as_ref(), &account_id, &ceremony_id, &credential)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+#[server]
+pub async fn start_passkey_login(
+	username: String,
+) -> Result<PasskeyChallenge<webauthn_rs_proto::RequestChallengeResponse>, ServerFnError<FriendVoteError>> {
+	use crate::accounts::Passkeys;
+	use crate::rate_limit::LoginLimiter;
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let (store, passkeys, LoginLimiter(limiter)) =
+		match (use_context::<Store>(), use_context::<Passkeys>(), use_context::<LoginLimiter>()) {
+			(Some(store), Some(passkeys), Some(limiter)) => (store, passkeys, limiter),
+			_ => {
+				return Err(FriendVoteError::Internal(
+					"Could not find store, passkeys or rate limiter in context.".to_string(),
+				)
+				.into_server_fn_error());
+			},
+		};
+	let client = client_address()
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+
+	let (ceremony_id, options) = passkeys
+		.start_login(store.as_ref(), &limiter, client, &username)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	Ok(PasskeyChallenge { ceremony_id, options })
+}
+
+#[server(input = leptos::server_fn::codec::Json)]
+pub async fn finish_passkey_login(
+	ceremony_id: String,
+	credential: webauthn_rs_proto::PublicKeyCredential,
+) -> Result<(), ServerFnError<FriendVoteError>> {
+	use crate::accounts::Passkeys;
+	use crate::store::Store;
+	use leptos::prelude::use_context;
+
+	let (store, passkeys) = match (use_context::<Store>(), use_context::<Passkeys>()) {
+		(Some(store), Some(passkeys)) => (store, passkeys),
+		_ => {
+			return Err(FriendVoteError::Internal("Could not find store or passkeys in context.".to_string())
+				.into_server_fn_error());
+		},
+	};
+
+	let account_id = passkeys
+		.finish_login(store.as_ref(), &ceremony_id, &credential)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)?;
+	log_in_session(store.as_ref(), &account_id)
+		.await
+		.map_err(FriendVoteError::into_server_fn_error)
+}
+
+/// Starts a session for the account, and redirects forms that were posted without JavaScript to the account page.
+#[cfg(feature = "ssr")]
+async fn log_in_session(
+	store: &dyn crate::store::ElectionStore,
+	account_id: &str,
+) -> Result<(), FriendVoteError> {
+	let cookie = crate::accounts::start_session(store, account_id, request_is_secure()).await?;
+	set_cookie(&cookie)?;
+	leptos_axum::redirect("/account");
+	Ok(())
+}
+
+#[cfg(feature = "ssr")]
+fn set_cookie(cookie: &str) -> Result<(), FriendVoteError> {
+	use leptos::prelude::use_context;
+
+	let response = use_context::<leptos_axum::ResponseOptions>()
+		.ok_or_else(|| FriendVoteError::Internal("Could not find response options in context.".to_string()))?;
+	let value =
+		http::HeaderValue::from_str(cookie).map_err(|error| FriendVoteError::Internal(error.to_string()))?;
+	response.append_header(http::header::SET_COOKIE, value);
+	Ok(())
+}
+
+#[cfg(feature = "ssr")]
+async fn logged_in_account_id(store: &dyn crate::store::ElectionStore) -> Result<String, FriendVoteError> {
+	crate::accounts::session_account_id(store, &request_headers())
+		.await?
+		.ok_or_else(|| FriendVoteError::Forbidden("Log in first.".to_string()))
+}
+
+/// An empty `admin_id` stands for the admin token of an election that the logged in account owns.
+#[cfg(feature = "ssr")]
+async fn resolve_admin_id(
+	store: &dyn crate::store::ElectionStore,
+	election_id: &str,
+	admin_id: String,
+) -> Result<String, FriendVoteError> {
+	if !admin_id.is_empty() {
+		return Ok(admin_id);
 	}
+	let account_id = crate::accounts::session_account_id(store, &request_headers())
+		.await?
+		.ok_or_else(|| {
+			FriendVoteError::Forbidden("Log in to manage this election without its admin link.".to_string())
+		})?;
+	crate::elections::owner_admin_id(store, election_id, &account_id).await
+}
 
-	Ok(ElectionResults {
-		election_name: db_entry.name,
-		candidates: db_entry.candidates,
-		n_votes: votes.len() as u64,
-		defeats_matrix: defeats_matrix,
-		ranked_candidates: ranked_candidates,
-	})
+/// The address of the client, to rate limit its attempts. Behind the [`TrustedProxy`](crate::rate_limit::TrustedProxy),
+/// it is the one the proxy forwards.
+#[cfg(feature = "ssr")]
+async fn client_address() -> Result<std::net::IpAddr, FriendVoteError> {
+	use axum::extract::ConnectInfo;
+	use leptos::prelude::use_context;
+	use std::net::SocketAddr;
+
+	let ConnectInfo(peer) = leptos_axum::extract::<ConnectInfo<SocketAddr>>()
+		.await
+		.map_err(|error| FriendVoteError::Internal(format!("Could not find the client address: {}", error)))?;
+	let trusted_proxy = use_context::<crate::rate_limit::TrustedProxy>().unwrap_or_default();
+	Ok(trusted_proxy.client_address(peer, &request_headers()))
+}
+
+#[cfg(feature = "ssr")]
+fn request_headers() -> http::HeaderMap {
+	use leptos::prelude::use_context;
+
+	use_context::<http::request::Parts>()
+		.map(|parts| parts.headers)
+		.unwrap_or_default()
+}
+
+/// Whether cookies should be restricted to HTTPS, as they are when the site is reached through it.
+#[cfg(feature = "ssr")]
+fn request_is_secure() -> bool {
+	request_origin().starts_with("https://")
+}
+
+/// The scheme and host the browser used to reach the server, for links that must be absolute.
+/// Behind a reverse proxy, its `X-Forwarded-Proto` and `X-Forwarded-Host` headers take precedence.
+#[cfg(feature = "ssr")]
+fn request_origin() -> String {
+	use leptos::prelude::use_context;
+
+	let Some(parts) = use_context::<http::request::Parts>() else {
+		return String::new();
+	};
+	let header = |name: &str| {
+		parts
+			.headers
+			.get(name)
+			.and_then(|value| value.to_str().ok())
+			// proxies may append their own value after the client's
+			.and_then(|value| value.split(',').next())
+			.map(|value| value.trim().to_string())
+	};
+	let scheme = header("x-forwarded-proto").unwrap_or_else(|| "http".to_string());
+	let host = header("x-forwarded-host")
+		.or_else(|| header("host"))
+		.unwrap_or_default();
+	format!("{}://{}", scheme, host)
 }
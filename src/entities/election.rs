@@ -9,9 +9,18 @@ pub struct Model {
 	pub election_id: String,
 	#[sea_orm(column_type = "Text")]
 	pub name: String,
-	pub candidates: Vec<String>,
 	pub admin_id: String,
 	pub result_id: String,
+	pub voting_open: bool,
+	pub ballot_count: i64,
+	pub results_version: i64,
+	pub last_vote_at: Option<DateTimeWithTimeZone>,
+	#[sea_orm(unique)]
+	pub join_code: Option<String>,
+	pub shuffle_candidates: bool,
+	pub review_ballots: bool,
+	pub owner_account_id: Option<String>,
+	pub members_only: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
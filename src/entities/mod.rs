@@ -2,5 +2,14 @@
 
 pub mod prelude;
 
+pub mod account;
+pub mod audit_entry;
+pub mod ballot_pattern;
+pub mod candidate;
 pub mod election;
+pub mod election_voter;
+pub mod pairwise_count;
+pub mod passkey;
+pub mod results_cache;
+pub mod session;
 pub mod votes;
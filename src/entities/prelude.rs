@@ -1,4 +1,13 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
+pub use super::account::Entity as Account;
+pub use super::audit_entry::Entity as AuditEntry;
+pub use super::ballot_pattern::Entity as BallotPattern;
+pub use super::candidate::Entity as Candidate;
 pub use super::election::Entity as Election;
+pub use super::election_voter::Entity as ElectionVoter;
+pub use super::pairwise_count::Entity as PairwiseCount;
+pub use super::passkey::Entity as Passkey;
+pub use super::results_cache::Entity as ResultsCache;
+pub use super::session::Entity as Session;
 pub use super::votes::Entity as Votes;
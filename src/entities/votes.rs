@@ -8,8 +8,8 @@ pub struct Model {
 	#[sea_orm(primary_key)]
 	pub vote_id: i32,
 	pub election_id: String,
-	pub cand_order: Vec<i32>,
-	pub comp_is_bigger: Vec<bool>,
+	#[sea_orm(column_type = "Text")]
+	pub ranking: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
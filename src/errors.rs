@@ -0,0 +1,174 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::str::FromStr;
+
+use http::StatusCode;
+use leptos::prelude::ServerFnError;
+
+/// Error type of all server functions and API endpoints.
+///
+/// The `Display` output is `<kind>: <message>`, which `FromStr` parses back,
+/// so the error survives the round trip from server to client.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum FriendVoteError {
+	#[error("NotFound: {0}")]
+	NotFound(String),
+	#[error("Forbidden: {0}")]
+	Forbidden(String),
+	/// The voter may not vote in this election, e.g. because they are not a member or already voted.
+	#[error("CannotVote: {0}")]
+	CannotVote(String),
+	#[error("Validation: {0}")]
+	Validation(String),
+	#[error("Closed: {0}")]
+	Closed(String),
+	#[error("TooManyRequests: {0}")]
+	TooManyRequests(String),
+	#[error("Internal: {0}")]
+	Internal(String),
+}
+
+impl FriendVoteError {
+	pub fn status_code(&self) -> StatusCode {
+		match self {
+			FriendVoteError::NotFound(_) => StatusCode::NOT_FOUND,
+			FriendVoteError::Forbidden(_) | FriendVoteError::CannotVote(_) => StatusCode::FORBIDDEN,
+			FriendVoteError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+			FriendVoteError::Closed(_) => StatusCode::CONFLICT,
+			FriendVoteError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+			FriendVoteError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+		}
+	}
+
+	pub fn message(&self) -> &str {
+		match self {
+			FriendVoteError::NotFound(message)
+			| FriendVoteError::Forbidden(message)
+			| FriendVoteError::CannotVote(message)
+			| FriendVoteError::Validation(message)
+			| FriendVoteError::Closed(message)
+			| FriendVoteError::TooManyRequests(message)
+			| FriendVoteError::Internal(message) => message,
+		}
+	}
+
+	/// Extracts the `FriendVoteError` from a server function error, treating all other failures as internal.
+	pub fn from_server_fn_error(error: &ServerFnError<FriendVoteError>) -> Self {
+		match error {
+			ServerFnError::WrappedServerError(error) => error.clone(),
+			other => FriendVoteError::Internal(other.to_string()),
+		}
+	}
+}
+
+impl FromStr for FriendVoteError {
+	type Err = ();
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (kind, message) = s.split_once(": ").ok_or(())?;
+		let message = message.to_string();
+		match kind {
+			"NotFound" => Ok(FriendVoteError::NotFound(message)),
+			"Forbidden" => Ok(FriendVoteError::Forbidden(message)),
+			"CannotVote" => Ok(FriendVoteError::CannotVote(message)),
+			"Validation" => Ok(FriendVoteError::Validation(message)),
+			"Closed" => Ok(FriendVoteError::Closed(message)),
+			"TooManyRequests" => Ok(FriendVoteError::TooManyRequests(message)),
+			"Internal" => Ok(FriendVoteError::Internal(message)),
+			_ => Err(()),
+		}
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl From<sea_orm::DbErr> for FriendVoteError {
+	fn from(value: sea_orm::DbErr) -> Self {
+		tracing::error!("database error: {}", value);
+		FriendVoteError::Internal("Database error.".to_string())
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl FriendVoteError {
+	/// Converts into a server function error, setting the HTTP status of the server function response.
+	pub fn into_server_fn_error(self) -> ServerFnError<FriendVoteError> {
+		if let Some(response_options) = leptos::prelude::use_context::<leptos_axum::ResponseOptions>() {
+			response_options.set_status(self.status_code());
+		}
+		ServerFnError::WrappedServerError(self)
+	}
+}
+
+#[cfg(feature = "ssr")]
+impl axum::response::IntoResponse for FriendVoteError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			self.status_code(),
+			axum::Json(crate::api::ApiErrorBody {
+				error: self.message().to_string(),
+			}),
+		)
+			.into_response()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn all_variants(message: &str) -> Vec<(FriendVoteError, StatusCode)> {
+		let message = message.to_string();
+		vec![
+			(FriendVoteError::NotFound(message.clone()), StatusCode::NOT_FOUND),
+			(FriendVoteError::Forbidden(message.clone()), StatusCode::FORBIDDEN),
+			(FriendVoteError::CannotVote(message.clone()), StatusCode::FORBIDDEN),
+			(FriendVoteError::Validation(message.clone()), StatusCode::UNPROCESSABLE_ENTITY),
+			(FriendVoteError::Closed(message.clone()), StatusCode::CONFLICT),
+			(FriendVoteError::TooManyRequests(message.clone()), StatusCode::TOO_MANY_REQUESTS),
+			(FriendVoteError::Internal(message), StatusCode::INTERNAL_SERVER_ERROR),
+		]
+	}
+
+	#[test]
+	fn errors_survive_the_round_trip_through_their_display() {
+		for message in ["Unknown election.", "Candidate: \"A: the first\" is unknown.", ""] {
+			for (error, _) in all_variants(message) {
+				assert_eq!(error.to_string().parse::<FriendVoteError>(), Ok(error.clone()));
+				assert_eq!(error.message(), message);
+			}
+		}
+	}
+
+	#[test]
+	fn errors_map_to_status_codes() {
+		for (error, status_code) in all_variants("message") {
+			assert_eq!(error.status_code(), status_code, "{error}");
+		}
+	}
+
+	#[test]
+	fn unknown_kinds_do_not_parse() {
+		assert_eq!("Unknown: message".parse::<FriendVoteError>(), Err(()));
+		assert_eq!("NotFound message".parse::<FriendVoteError>(), Err(()));
+		assert_eq!("notfound: message".parse::<FriendVoteError>(), Err(()));
+	}
+
+	#[test]
+	fn other_server_fn_errors_are_internal() {
+		let error = FriendVoteError::Closed("Voting is closed.".to_string());
+		assert_eq!(
+			FriendVoteError::from_server_fn_error(&ServerFnError::WrappedServerError(error.clone())),
+			error
+		);
+		let other = ServerFnError::<FriendVoteError>::Request("connection refused".to_string());
+		assert!(matches!(FriendVoteError::from_server_fn_error(&other), FriendVoteError::Internal(_)));
+	}
+}
@@ -9,14 +9,30 @@
 /// You should have received a copy of the GNU General Public License along with FriendVote.
 /// If not, see <https://www.gnu.org/licenses/>.
 pub mod app;
+mod components;
 mod endpoints;
+mod errors;
 
 #[cfg(feature = "ssr")]
-mod db;
+pub mod accounts;
+#[cfg(feature = "ssr")]
+pub mod api;
+#[cfg(feature = "ssr")]
+pub mod ballot;
+#[cfg(feature = "ssr")]
+mod elections;
 #[cfg(feature = "ssr")]
 mod entities;
 #[cfg(feature = "ssr")]
-mod ranked_pairs;
+pub mod oidc;
+#[cfg(feature = "ssr")]
+mod qr;
+#[cfg(feature = "ssr")]
+pub mod ranked_pairs;
+#[cfg(feature = "ssr")]
+pub mod rate_limit;
+#[cfg(feature = "ssr")]
+pub mod store;
 
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
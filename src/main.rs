@@ -11,10 +11,16 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
+	use std::net::SocketAddr;
+	use std::sync::Arc;
 	use std::time::Duration;
 
 	use axum::Router;
+	use friendvote::accounts::{PasskeyVerifier, Passkeys};
 	use friendvote::app::*;
+	use friendvote::oidc::{Oidc, OidcConfig, OidcProvider};
+	use friendvote::rate_limit::{JoinCodeLimiter, LoginLimiter, RateLimiter, TrustedProxy};
+	use friendvote::store::{DbStore, MemoryStore, ResultsCacheMode, Store};
 	use leptos::logging::log;
 	use leptos::prelude::*;
 	use leptos_axum::{generate_route_list, LeptosRoutes};
@@ -30,28 +36,105 @@ async fn main() {
 
 	// Set up db connection
 	// postgres://<user>:<passwd>@localhost/friendvote?currentSchema=public
+	// or sqlite://friendvote.sqlite?mode=rwc
+	// or memory, to keep everything in memory
 	let db_connection_str = std::env::var("DATABASE_URL").expect("env variable DATABASE_URL not set");
 	tracing_subscriber::fmt()
 		.with_max_level(tracing::Level::DEBUG)
 		.with_test_writer()
 		.init();
-	let mut db_options = ConnectOptions::new(db_connection_str.clone());
-	db_options
-		.max_connections(5)
-		.acquire_timeout(Duration::from_secs(3))
-		.sqlx_logging(false); // Disable SQLx log
-	let db_conn = Database::connect(db_options)
-		.await
-		.expect("unable to connect to database");
-	Migrator::up(&db_conn, None)
-		.await
-		.expect("migration did not succeed");
+	let store: Store = if db_connection_str == "memory" {
+		Arc::new(MemoryStore::new())
+	} else {
+		let mut db_options = ConnectOptions::new(db_connection_str.clone());
+		db_options
+			.max_connections(5)
+			.acquire_timeout(Duration::from_secs(3))
+			.sqlx_logging(false); // Disable SQLx log
+		let db_conn = Database::connect(db_options)
+			.await
+			.expect("unable to connect to database");
+		Migrator::up(&db_conn, None)
+			.await
+			.expect("migration did not succeed");
+		// with several server instances, RESULTS_CACHE=database lets them share computed results
+		let results_cache_mode = match std::env::var("RESULTS_CACHE").as_deref() {
+			Ok("database") => ResultsCacheMode::Database,
+			Ok("memory") | Err(_) => ResultsCacheMode::Memory,
+			Ok(other) => panic!("unknown RESULTS_CACHE {}, expected memory or database", other),
+		};
+		Arc::new(DbStore::new(db_conn, results_cache_mode))
+	};
 
-	let app = Router::new()
-		.leptos_routes_with_context(&leptos_options, routes, move || provide_context(db_conn.clone()), {
-			let leptos_options = leptos_options.clone();
-			move || shell(leptos_options.clone())
-		})
+	if std::env::args().nth(1).as_deref() == Some("rebuild-tallies") {
+		// recompute the pairwise counts and ballot patterns of every election from its ballots, then exit
+		let election_ids = store.get_election_ids().await.expect("could not list elections");
+		for election_id in election_ids.iter() {
+			match store.rebuild_tallies(election_id).await {
+				Ok(true) => log!("rebuilt tally of election {}", election_id),
+				Ok(false) => {},
+				Err(error) => log!("could not rebuild tally of election {}: {}", election_id, error),
+			}
+		}
+		log!("checked {} elections", election_ids.len());
+		return;
+	}
+
+	// behind a reverse proxy, TRUSTED_PROXY=<its IP address> rate limits the clients it forwards one by one
+	let trusted_proxy = match std::env::var("TRUSTED_PROXY") {
+		Ok(address) => TrustedProxy(Some(
+			address
+				.parse()
+				.unwrap_or_else(|_| panic!("TRUSTED_PROXY {} is not an IP address", address)),
+		)),
+		Err(_) => TrustedProxy(None),
+	};
+	// each address gets a few join code attempts per minute, far too few to guess codes
+	let join_code_limiter: JoinCodeLimiter = Arc::new(RateLimiter::new(10, Duration::from_secs(60)));
+	// and a few login attempts, far too few to guess passwords, per client address resolved like for join codes
+	let login_limiter = LoginLimiter(Arc::new(RateLimiter::new(10, Duration::from_secs(60))));
+	// passkeys only work on the address that users type in their browser, e.g. https://vote.example.com
+	let public_url =
+		std::env::var("PUBLIC_URL").unwrap_or_else(|_| format!("http://localhost:{}", addr.port()));
+	let public_url = public_url.parse().expect("PUBLIC_URL is not a valid URL");
+	let passkeys: Passkeys = Arc::new(PasskeyVerifier::new(&public_url).expect("could not set up passkeys"));
+	// setting OIDC_ISSUER_URL lets organizers and voters log in through that OpenID Connect provider
+	let oidc: Option<Oidc> = match OidcConfig::from_env() {
+		Ok(Some(config)) => Some(Arc::new(
+			OidcProvider::discover(config, &public_url)
+				.await
+				.expect("could not set up OIDC"),
+		)),
+		Ok(None) => None,
+		Err(error) => {
+			log!("OIDC is disabled: {}", error.message());
+			None
+		},
+	};
+	let mut app = Router::new()
+		.nest("/api/v1", friendvote::api::router(store.clone(), join_code_limiter.clone(), trusted_proxy));
+	if let Some(oidc) = oidc.clone() {
+		app = app.nest("/auth/oidc", friendvote::oidc::router(store.clone(), oidc));
+	}
+	let app = app
+		.leptos_routes_with_context(
+			&leptos_options,
+			routes,
+			move || {
+				provide_context(store.clone());
+				provide_context(join_code_limiter.clone());
+				provide_context(login_limiter.clone());
+				provide_context(trusted_proxy);
+				provide_context(passkeys.clone());
+				if let Some(oidc) = oidc.clone() {
+					provide_context(oidc);
+				}
+			},
+			{
+				let leptos_options = leptos_options.clone();
+				move || shell(leptos_options.clone())
+			},
+		)
 		.fallback(leptos_axum::file_and_error_handler(shell))
 		.with_state(leptos_options);
 
@@ -59,7 +142,10 @@ async fn main() {
 	// `axum::Server` is a re-export of `hyper::Server`
 	log!("listening on http://{}", &addr);
 	let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-	axum::serve(listener, app.into_make_service()).await.unwrap();
+	// the client addresses are needed to rate limit join codes and logins
+	axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+		.await
+		.unwrap();
 }
 
 #[cfg(not(feature = "ssr"))]
@@ -0,0 +1,380 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::get;
+use axum::{Extension, Router};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use http::header::SET_COOKIE;
+use http::HeaderMap;
+use nanoid::nanoid;
+use openidconnect::core::{CoreAuthenticationFlow, CoreClient, CoreProviderMetadata};
+use openidconnect::url::Url;
+use openidconnect::{
+	reqwest, AuthorizationCode, ClientId, ClientSecret, CsrfToken, EndpointMaybeSet, EndpointNotSet,
+	EndpointSet, IssuerUrl, Nonce, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, Scope,
+};
+use webauthn_rs::prelude::Uuid;
+
+use crate::errors::FriendVoteError;
+use crate::store::{Account, ElectionStore, Store};
+
+/// Shared by the server functions and the login routes, like the store. Absent when OIDC is not configured.
+pub type Oidc = Arc<OidcProvider>;
+
+/// Logins that do not come back from the provider within this time are abandoned.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Where the provider sends the browser back to, relative to the public URL.
+const CALLBACK_PATH: &str = "/auth/oidc/callback";
+/// Name of the cookie that ties a login to the browser that started it, so that a callback with another
+/// browser's `state` cannot log this one in.
+const STATE_COOKIE: &str = "friendvote_oidc_state";
+/// Attempts at a free username, when the one from the provider is taken by another account.
+const USERNAME_ATTEMPTS: usize = 5;
+
+/// The client as set up from the provider's discovery document, which always has an authorization endpoint.
+type DiscoveredClient = CoreClient<
+	EndpointSet,
+	EndpointNotSet,
+	EndpointNotSet,
+	EndpointNotSet,
+	EndpointMaybeSet,
+	EndpointMaybeSet,
+>;
+
+/// The OpenID Connect settings of the server, from its environment variables.
+pub struct OidcConfig {
+	pub issuer_url: String,
+	pub client_id: String,
+	/// Public clients have no secret, and rely on PKCE alone.
+	pub client_secret: Option<String>,
+	/// Shown on the login button, e.g. "Log in with Example Corp".
+	pub provider_name: String,
+	/// Only members of this group can vote in members-only elections. Without it, everyone who logs in can.
+	pub voter_group: Option<String>,
+	/// The ID token claim that lists the groups of the subject.
+	pub groups_claim: String,
+	/// Requested besides `openid`, e.g. `groups` for providers that only send the groups when asked.
+	pub scopes: Vec<String>,
+}
+
+impl OidcConfig {
+	/// Returns `None` when `OIDC_ISSUER_URL` is not set, which leaves OIDC disabled,
+	/// and an error when it is set without `OIDC_CLIENT_ID`.
+	pub fn from_env() -> Result<Option<Self>, FriendVoteError> {
+		let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+		let Some(issuer_url) = var("OIDC_ISSUER_URL") else {
+			return Ok(None);
+		};
+		let client_id = var("OIDC_CLIENT_ID").ok_or_else(|| {
+			FriendVoteError::Internal("OIDC_ISSUER_URL is set, but OIDC_CLIENT_ID is not.".to_string())
+		})?;
+		Ok(Some(Self {
+			issuer_url,
+			client_id,
+			client_secret: var("OIDC_CLIENT_SECRET"),
+			provider_name: var("OIDC_PROVIDER_NAME").unwrap_or_else(|| "OpenID Connect".to_string()),
+			voter_group: var("OIDC_VOTER_GROUP"),
+			groups_claim: var("OIDC_GROUPS_CLAIM").unwrap_or_else(|| "groups".to_string()),
+			scopes: var("OIDC_SCOPES")
+				.unwrap_or_else(|| "profile email".to_string())
+				.split_whitespace()
+				.filter(|scope| *scope != "openid")
+				.map(str::to_string)
+				.collect(),
+		}))
+	}
+}
+
+struct PendingLogin {
+	pkce_verifier: PkceCodeVerifier,
+	nonce: Nonce,
+	return_to: String,
+}
+
+/// Logs organizers and voters in through an OpenID Connect provider, with the authorization code flow and PKCE.
+/// The state of a login stays in memory until the provider sends the browser back,
+/// so a login must finish on the server instance that started it.
+pub struct OidcProvider {
+	client: DiscoveredClient,
+	http_client: reqwest::Client,
+	provider_name: String,
+	voter_group: Option<String>,
+	groups_claim: String,
+	scopes: Vec<String>,
+	/// Whether the session cookie is restricted to HTTPS.
+	secure: bool,
+	/// When each login started, and its state, keyed by the `state` parameter sent to the provider.
+	logins: Mutex<HashMap<String, (Instant, PendingLogin)>>,
+}
+
+impl OidcProvider {
+	/// Fetches the provider's discovery document and signing keys, so the provider must be up when the server starts.
+	/// `public_url` is the address at which users reach the site, to which the provider sends them back.
+	pub async fn discover(config: OidcConfig, public_url: &Url) -> Result<Self, FriendVoteError> {
+		let setup_error =
+			|error: String| FriendVoteError::Internal(format!("Could not set up OIDC: {}", error));
+		// following redirects would expose the token requests to server-side request forgery
+		let http_client = reqwest::ClientBuilder::new()
+			.redirect(reqwest::redirect::Policy::none())
+			.build()
+			.map_err(|error| setup_error(error.to_string()))?;
+		let issuer_url = IssuerUrl::new(config.issuer_url).map_err(|error| setup_error(error.to_string()))?;
+		let metadata = CoreProviderMetadata::discover_async(issuer_url, &http_client)
+			.await
+			.map_err(|error| setup_error(error.to_string()))?;
+		let redirect_url = public_url
+			.join(CALLBACK_PATH)
+			.map_err(|error| setup_error(error.to_string()))?;
+		let client = CoreClient::from_provider_metadata(
+			metadata,
+			ClientId::new(config.client_id),
+			config.client_secret.map(ClientSecret::new),
+		)
+		.set_redirect_uri(RedirectUrl::from_url(redirect_url));
+		Ok(Self {
+			client,
+			http_client,
+			provider_name: config.provider_name,
+			voter_group: config.voter_group,
+			groups_claim: config.groups_claim,
+			scopes: config.scopes,
+			secure: public_url.scheme() == "https",
+			logins: Mutex::default(),
+		})
+	}
+
+	pub fn provider_name(&self) -> &str {
+		&self.provider_name
+	}
+
+	/// Returns the address of the provider's login page, which sends the browser back to `return_to` afterwards,
+	/// and the `state` of the login, which the browser must bring back in its state cookie too.
+	pub fn start_login(&self, return_to: String) -> (Url, String) {
+		let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+		let mut request = self
+			.client
+			.authorize_url(CoreAuthenticationFlow::AuthorizationCode, CsrfToken::new_random, Nonce::new_random)
+			.set_pkce_challenge(pkce_challenge);
+		for scope in self.scopes.iter() {
+			request = request.add_scope(Scope::new(scope.clone()));
+		}
+		let (url, state, nonce) = request.url();
+
+		let now = Instant::now();
+		let mut logins = self.logins.lock().expect("oidc logins lock poisoned");
+		logins.retain(|_, (started, _)| now.duration_since(*started) < LOGIN_TIMEOUT);
+		logins.insert(
+			state.secret().clone(),
+			(
+				now,
+				PendingLogin {
+					pkce_verifier,
+					nonce,
+					return_to,
+				},
+			),
+		);
+		(url, state.into_secret())
+	}
+
+	/// A login can be finished only once, whether it succeeds or not.
+	fn take(&self, state: &str) -> Result<PendingLogin, FriendVoteError> {
+		let mut logins = self.logins.lock().expect("oidc logins lock poisoned");
+		logins
+			.remove(state)
+			.filter(|(started, _)| started.elapsed() < LOGIN_TIMEOUT)
+			.map(|(_, login)| login)
+			.ok_or_else(|| FriendVoteError::Forbidden("This login expired, start it again.".to_string()))
+	}
+
+	/// Redeems the authorization code for an ID token, and returns the account of its subject,
+	/// created on its first login, and where to send the browser.
+	pub async fn finish_login(
+		&self,
+		store: &dyn ElectionStore,
+		state: &str,
+		code: String,
+	) -> Result<(String, String), FriendVoteError> {
+		let login = self.take(state)?;
+		let refused = |error: String| {
+			FriendVoteError::Forbidden(format!("The identity provider refused the login: {}", error))
+		};
+		let token_response = self
+			.client
+			.exchange_code(AuthorizationCode::new(code))
+			.map_err(|error| FriendVoteError::Internal(error.to_string()))?
+			.set_pkce_verifier(login.pkce_verifier)
+			.request_async(&self.http_client)
+			.await
+			.map_err(|error| refused(error.to_string()))?;
+		let id_token = token_response
+			.extra_fields()
+			.id_token()
+			.ok_or_else(|| refused("no ID token".to_string()))?;
+		// checks the signature, issuer, audience, expiry and nonce
+		let claims = id_token
+			.claims(&self.client.id_token_verifier(), &login.nonce)
+			.map_err(|error| refused(error.to_string()))?;
+		let subject = claims.subject().to_string();
+		let username = claims
+			.preferred_username()
+			.map(|username| username.to_string())
+			.or_else(|| claims.email().map(|email| email.to_string()))
+			.unwrap_or_else(|| subject.clone());
+		let member = match &self.voter_group {
+			Some(voter_group) => token_groups(&id_token.to_string(), &self.groups_claim).contains(voter_group),
+			None => true,
+		};
+		let account_id = link_account(store, &subject, &username, member).await?;
+		Ok((account_id, login.return_to))
+	}
+}
+
+/// Reads the groups from the payload of an ID token that was already verified. A single group may be
+/// given as a string rather than a list.
+fn token_groups(id_token: &str, groups_claim: &str) -> Vec<String> {
+	let payload = id_token
+		.split('.')
+		.nth(1)
+		.and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+		.and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok());
+	match payload.as_ref().and_then(|payload| payload.get(groups_claim)) {
+		Some(serde_json::Value::Array(groups)) => groups
+			.iter()
+			.filter_map(|group| group.as_str().map(str::to_string))
+			.collect(),
+		Some(serde_json::Value::String(group)) => vec![group.clone()],
+		_ => Vec::new(),
+	}
+}
+
+/// Returns the account of the subject, creating it on the first login. Accounts are never linked by username,
+/// as the provider's usernames may clash with those chosen at sign up. Group membership is updated on every login.
+async fn link_account(
+	store: &dyn ElectionStore,
+	subject: &str,
+	username: &str,
+	member: bool,
+) -> Result<String, FriendVoteError> {
+	if let Some(account) = store.find_account_by_oidc_subject(subject).await? {
+		store.set_oidc_member(&account.account_id, member).await?;
+		return Ok(account.account_id);
+	}
+	let account_id = Uuid::new_v4().to_string();
+	for attempt in 0..USERNAME_ATTEMPTS {
+		let username = if attempt == 0 {
+			username.to_string()
+		} else {
+			format!("{}-{}", username, nanoid!(4))
+		};
+		let inserted = store
+			.insert_account(Account {
+				account_id: account_id.clone(),
+				username,
+				password_hash: None,
+				oidc_subject: Some(subject.to_string()),
+				oidc_member: member,
+			})
+			.await?;
+		if inserted {
+			return Ok(account_id);
+		}
+	}
+	Err(FriendVoteError::Internal("Could not find a free username.".to_string()))
+}
+
+/// Builds the `/auth/oidc` router, with the routes that the login buttons and the provider send browsers to.
+pub fn router<S>(store: Store, oidc: Oidc) -> Router<S>
+where
+	S: Clone + Send + Sync + 'static,
+{
+	Router::new()
+		.route("/login", get(login))
+		.route("/callback", get(callback))
+		.layer(Extension(oidc))
+		.with_state(store)
+}
+
+#[derive(serde::Deserialize)]
+struct LoginQuery {
+	return_to: Option<String>,
+}
+
+async fn login(Extension(oidc): Extension<Oidc>, Query(query): Query<LoginQuery>) -> Response {
+	// only paths on this site, so that the login cannot be used to send browsers elsewhere
+	let return_to = query
+		.return_to
+		.filter(|path| path.starts_with('/') && !path.starts_with("//") && !path.contains('\\'))
+		.unwrap_or_else(|| "/account".to_string());
+	let (url, state) = oidc.start_login(return_to);
+	let cookie = state_cookie(&state, LOGIN_TIMEOUT.as_secs(), oidc.secure);
+	([(SET_COOKIE, cookie)], Redirect::to(url.as_str())).into_response()
+}
+
+/// Only sent to the login routes, and along with the provider's redirect back, which is a top-level navigation.
+fn state_cookie(state: &str, max_age_seconds: u64, secure: bool) -> String {
+	let cookie = format!(
+		"{}={}; Path=/auth/oidc; Max-Age={}; HttpOnly; SameSite=Lax",
+		STATE_COOKIE, state, max_age_seconds
+	);
+	if secure {
+		cookie + "; Secure"
+	} else {
+		cookie
+	}
+}
+
+#[derive(serde::Deserialize)]
+struct CallbackQuery {
+	state: Option<String>,
+	code: Option<String>,
+	error: Option<String>,
+}
+
+async fn callback(
+	State(store): State<Store>,
+	Extension(oidc): Extension<Oidc>,
+	Query(query): Query<CallbackQuery>,
+	headers: HeaderMap,
+) -> Response {
+	let result = async {
+		let (Some(state), Some(code)) = (query.state, query.code) else {
+			return Err(FriendVoteError::Forbidden(format!(
+				"The identity provider refused the login: {}",
+				query.error.as_deref().unwrap_or("no authorization code")
+			)));
+		};
+		if crate::accounts::cookie_value(&headers, STATE_COOKIE).as_deref() != Some(state.as_str()) {
+			return Err(FriendVoteError::Forbidden(
+				"This login was started in another browser, start it again.".to_string(),
+			));
+		}
+		let (account_id, return_to) = oidc.finish_login(store.as_ref(), &state, code).await?;
+		let cookie = crate::accounts::start_session(store.as_ref(), &account_id, oidc.secure).await?;
+		Ok(([(SET_COOKIE, cookie)], Redirect::to(&return_to)).into_response())
+	}
+	.await;
+	// browsers land here, so errors are shown as text rather than as the API's JSON
+	let mut response = result.unwrap_or_else(|error: FriendVoteError| {
+		(error.status_code(), error.message().to_string()).into_response()
+	});
+	// the state is used up, whether the login succeeded or not
+	if let Ok(cookie) = state_cookie("", 0, oidc.secure).parse() {
+		response.headers_mut().append(SET_COOKIE, cookie);
+	}
+	response
+}
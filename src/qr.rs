@@ -0,0 +1,34 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+
+use crate::errors::FriendVoteError;
+
+/// Renders `url` as a QR code, in an SVG image that can be inlined in a page.
+/// The image keeps a fixed size unless the page styles it, e.g. with `width: 100%`.
+pub fn qr_code_svg(url: &str) -> Result<String, FriendVoteError> {
+	// medium error correction still scans from the back of a room, without making the code too dense
+	let code = QrCode::with_error_correction_level(url, EcLevel::M).map_err(|error| {
+		FriendVoteError::Internal(format!("Could not encode {} as a QR code: {}", url, error))
+	})?;
+	let image = code
+		.render::<svg::Color>()
+		.min_dimensions(200, 200)
+		.dark_color(svg::Color("#000000"))
+		.light_color(svg::Color("#ffffff"))
+		.build();
+	// drop the XML declaration, which has no place inside HTML
+	Ok(match image.find("<svg") {
+		Some(start) => image[start..].to_string(),
+		None => image,
+	})
+}
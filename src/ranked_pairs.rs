@@ -8,126 +8,237 @@
 /// See the GNU General Public License for more details.
 /// You should have received a copy of the GNU General Public License along with FriendVote.
 /// If not, see <https://www.gnu.org/licenses/>.
-#[cfg(feature = "ssr")]
+use std::cmp::Reverse;
+
+struct Defeat {
+	winner: usize,
+	loser: usize,
+	votes_for: u64,
+	votes_against: u64,
+}
+
+/// Square matrix of bits, with each row packed in `u64` words.
+struct BitMatrix {
+	words_per_row: usize,
+	words: Vec<u64>,
+}
+
+impl BitMatrix {
+	fn new(size: usize) -> Self {
+		let words_per_row = size.div_ceil(64);
+		Self {
+			words_per_row,
+			words: vec![0; words_per_row * size],
+		}
+	}
+
+	fn get(&self, row: usize, col: usize) -> bool {
+		self.words[row * self.words_per_row + col / 64] & (1 << (col % 64)) != 0
+	}
+
+	fn set(&mut self, row: usize, col: usize) {
+		self.words[row * self.words_per_row + col / 64] |= 1 << (col % 64);
+	}
+
+	fn unset(&mut self, row: usize, col: usize) {
+		self.words[row * self.words_per_row + col / 64] &= !(1 << (col % 64));
+	}
+
+	/// Sets every bit of row `dst` that is set in row `src`.
+	fn or_row(&mut self, dst: usize, src: usize) {
+		for word in 0..self.words_per_row {
+			self.words[dst * self.words_per_row + word] |= self.words[src * self.words_per_row + word];
+		}
+	}
+
+	/// Iterates over the columns of the bits set in `row`.
+	fn ones(&self, row: usize) -> impl Iterator<Item = usize> + '_ {
+		self.words[(row * self.words_per_row)..((row + 1) * self.words_per_row)]
+			.iter()
+			.enumerate()
+			.flat_map(|(word_ind, word)| {
+				(0..64)
+					.filter(move |bit| word & (1 << bit) != 0)
+					.map(move |bit| word_ind * 64 + bit)
+			})
+	}
+}
+
 /// Tideman's ranked pairs (winning variant)
-pub fn calculate_ranks(defeats_matrix: &Vec<Vec<Option<u64>>>) -> std::collections::HashMap<usize, usize> {
-	//(Vec<Vec<bool>>, Vec<Vec<usize>>) {
+///
+/// Defeats are locked in from strongest to weakest, where defeats of equal strength are locked in together.
+/// Locking stops at the first group that would create a cycle, which is then left out entirely.
+/// Returns the rank of every candidate: the length of the longest chain of locked defeats leading to it,
+/// so unbeaten candidates have rank 0.
+///
+/// Which candidates reach which is kept up to date after every locked defeat,
+/// so checking a defeat for cycles takes constant time and locking it at most `O(n²/64)`.
+pub fn calculate_ranks(defeats_matrix: &[Vec<Option<u64>>]) -> Vec<usize> {
+	let n_candidates = defeats_matrix.len();
 
 	// get sorted vec of defeats
-
-	use std::collections::{HashMap, HashSet};
-
-	use petgraph::{graph::NodeIndex, visit::EdgeRef};
-	let mut defeats = Vec::<(usize, usize, u64, u64)>::new();
-	{
-		for row_ind in 0..defeats_matrix.len() {
-			for col_ind in 0..row_ind {
-				if defeats_matrix[row_ind][col_ind] > defeats_matrix[col_ind][row_ind] {
-					defeats.push((
-						row_ind,
-						col_ind,
-						defeats_matrix[row_ind][col_ind].unwrap(),
-						defeats_matrix[col_ind][row_ind].unwrap(),
-					));
-				} else if defeats_matrix[row_ind][col_ind] < defeats_matrix[col_ind][row_ind] {
-					defeats.push((
-						col_ind,
-						row_ind,
-						defeats_matrix[col_ind][row_ind].unwrap(),
-						defeats_matrix[row_ind][col_ind].unwrap(),
-					));
+	let mut defeats = Vec::new();
+	for (winner, defeats_row) in defeats_matrix.iter().enumerate() {
+		for (loser, votes_for) in defeats_row.iter().enumerate() {
+			if let (Some(votes_for), Some(votes_against)) = (votes_for, defeats_matrix[loser][winner]) {
+				if *votes_for > votes_against {
+					defeats.push(Defeat {
+						winner,
+						loser,
+						votes_for: *votes_for,
+						votes_against,
+					});
 				}
 			}
 		}
-		let max_defeat_majority = defeats.iter().map(|d| d.2).max().unwrap();
-		defeats.sort_by_key(|defeat| (max_defeat_majority - defeat.2, defeat.3));
-	}
-
-	// group defeats by strength
-	let mut defeats_grouped_ind = Vec::new();
-	{
-		let mut prev_key = (defeats[0].2, defeats[0].3);
-		let mut defeats_group_ind = Vec::new();
-		for (defeat_ind, defeat) in defeats.iter().enumerate() {
-			if (defeat.2, defeat.3) != prev_key {
-				defeats_grouped_ind.push(defeats_group_ind.clone());
-				defeats_group_ind = Vec::new();
-				defeats_group_ind.push(defeat_ind);
-				prev_key = (defeat.2, defeat.3);
-			} else {
-				defeats_group_ind.push(defeat_ind);
-			}
-		}
-		if !defeats_group_ind.is_empty() {
-			defeats_grouped_ind.push(defeats_group_ind.clone());
-		}
 	}
+	defeats.sort_unstable_by_key(|defeat| (Reverse(defeat.votes_for), defeat.votes_against));
 
-	// create graph
-	let mut defeat_graph = petgraph::stable_graph::StableDiGraph::<usize, ()>::new();
-	let mut nodes = Vec::with_capacity(defeats_matrix.len());
-	{
-		for candidate_index in 0usize..defeats_matrix.len() {
-			let node_ind = defeat_graph.add_node(candidate_index);
-			nodes.push(node_ind);
-		}
+	// lock in defeats, group by group
+	let mut locked = BitMatrix::new(n_candidates);
+	let mut reaches = BitMatrix::new(n_candidates);
+	for candidate in 0..n_candidates {
+		reaches.set(candidate, candidate);
 	}
-	let mut ac_defeat_graph = petgraph::acyclic::Acyclic::try_from_graph(defeat_graph).unwrap();
-	{
-		let mut new_edges = Vec::new();
-		let mut defeat;
-		let mut cycle_found = false;
-		for defeats_group in defeats_grouped_ind {
-			new_edges.clear();
-			for defeat_ind in defeats_group.iter() {
-				defeat = defeats.get(*defeat_ind).unwrap();
-				match ac_defeat_graph.try_add_edge(nodes[defeat.0], nodes[defeat.1], ()) {
-					Ok(edge_ind) => new_edges.push(edge_ind),
-					Err(_) => {
-						// cycle found, abort adding this defeats group
-						for edge_id in new_edges.iter() {
-							ac_defeat_graph.remove_edge(*edge_id);
-						}
-						cycle_found = true;
-						break;
-					},
-				};
+	let strength = |defeat: &Defeat| (defeat.votes_for, defeat.votes_against);
+	'locking: for defeats_group in defeats.chunk_by(|first, second| strength(first) == strength(second)) {
+		for (defeat_ind, defeat) in defeats_group.iter().enumerate() {
+			if reaches.get(defeat.loser, defeat.winner) {
+				// cycle found, roll back this group and stop
+				for locked_defeat in defeats_group[..defeat_ind].iter() {
+					locked.unset(locked_defeat.winner, locked_defeat.loser);
+				}
+				break 'locking;
 			}
-			if cycle_found {
-				break;
+			locked.set(defeat.winner, defeat.loser);
+			if !reaches.get(defeat.winner, defeat.loser) {
+				// everything that reaches the winner now also reaches everything the loser reaches
+				for candidate in 0..n_candidates {
+					if reaches.get(candidate, defeat.winner) {
+						reaches.or_row(candidate, defeat.loser);
+					}
+				}
 			}
 		}
 	}
 
-	// find node ranks
-	let mut node_ranks = nodes
-		.iter()
-		.map(|n| (*n, nodes.len()))
-		.collect::<HashMap<NodeIndex, usize>>();
-	{
-		let mut current_layer = 0;
-		let mut current_layer_nodes = HashSet::new();
-		for node in nodes.iter() {
-			if ac_defeat_graph.edges_directed(*node, petgraph::Incoming).count() == 0 {
-				current_layer_nodes.insert(*node);
-			}
+	// find ranks as longest paths, visiting the candidates in topological order
+	let mut n_unvisited_winners = vec![0usize; n_candidates];
+	for winner in 0..n_candidates {
+		for loser in locked.ones(winner) {
+			n_unvisited_winners[loser] += 1;
 		}
-		let mut next_layer_nodes;
-		while !current_layer_nodes.is_empty() {
-			next_layer_nodes = HashSet::new();
-			for node in current_layer_nodes.iter() {
-				node_ranks.insert(*node, current_layer);
-				for edge in ac_defeat_graph.edges_directed(*node, petgraph::Outgoing) {
-					next_layer_nodes.insert(edge.target());
-				}
+	}
+	let mut ranks = vec![0; n_candidates];
+	let mut to_visit = (0..n_candidates)
+		.filter(|candidate| n_unvisited_winners[*candidate] == 0)
+		.collect::<Vec<_>>();
+	while let Some(winner) = to_visit.pop() {
+		for loser in locked.ones(winner) {
+			ranks[loser] = ranks[loser].max(ranks[winner] + 1);
+			n_unvisited_winners[loser] -= 1;
+			if n_unvisited_winners[loser] == 0 {
+				to_visit.push(loser);
 			}
-			current_layer_nodes = next_layer_nodes;
-			current_layer += 1;
 		}
 	}
+	ranks
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Builds the defeats matrix from `(winner, loser, votes for the winner)`, with 0 for the other pairs.
+	fn defeats_matrix(n_candidates: usize, defeats: &[(usize, usize, u64)]) -> Vec<Vec<Option<u64>>> {
+		let mut defeats_matrix = (0..n_candidates)
+			.map(|winner| {
+				(0..n_candidates)
+					.map(|loser| (winner != loser).then_some(0))
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>();
+		for (winner, loser, votes_for) in defeats {
+			defeats_matrix[*winner][*loser] = Some(*votes_for);
+		}
+		defeats_matrix
+	}
 
-	node_ranks
-		.iter()
-		.map(|(node_id, rank)| (*ac_defeat_graph.node_weight(*node_id).unwrap(), *rank))
-		.collect()
+	#[test]
+	fn ranks_without_cycles_follow_the_defeats() {
+		// the Tennessee capital example: 42% Memphis, 26% Nashville, 15% Chattanooga, 17% Knoxville first
+		let (memphis, nashville, chattanooga, knoxville) = (0, 1, 2, 3);
+		let defeats_matrix = defeats_matrix(
+			4,
+			&[
+				(memphis, nashville, 42),
+				(memphis, chattanooga, 42),
+				(memphis, knoxville, 42),
+				(nashville, memphis, 58),
+				(nashville, chattanooga, 68),
+				(nashville, knoxville, 68),
+				(chattanooga, memphis, 58),
+				(chattanooga, nashville, 32),
+				(chattanooga, knoxville, 83),
+				(knoxville, memphis, 58),
+				(knoxville, nashville, 32),
+				(knoxville, chattanooga, 17),
+			],
+		);
+		assert_eq!(calculate_ranks(&defeats_matrix), vec![3, 0, 1, 2]);
+	}
+
+	#[test]
+	fn no_defeats_ranks_everyone_first() {
+		assert_eq!(calculate_ranks(&defeats_matrix(3, &[])), vec![0, 0, 0]);
+		assert_eq!(calculate_ranks(&defeats_matrix(3, &[(0, 1, 4), (1, 0, 4)])), vec![0, 0, 0]);
+		assert_eq!(calculate_ranks(&defeats_matrix(1, &[])), vec![0]);
+		assert_eq!(calculate_ranks(&[]), Vec::<usize>::new());
+	}
+
+	#[test]
+	fn tied_candidates_share_a_rank() {
+		// 0 beats 1 and 2, which are tied, and both beat 3
+		let defeats_matrix =
+			defeats_matrix(4, &[(0, 1, 5), (0, 2, 5), (0, 3, 5), (1, 2, 2), (2, 1, 2), (1, 3, 4), (2, 3, 3)]);
+		assert_eq!(calculate_ranks(&defeats_matrix), vec![0, 1, 1, 2]);
+	}
+
+	#[test]
+	fn weakest_defeat_of_a_cycle_is_dropped() {
+		// 0 beats 1 beats 2 beats 0, the last one narrowly
+		let defeats_matrix =
+			defeats_matrix(3, &[(0, 1, 7), (1, 0, 2), (1, 2, 6), (2, 1, 3), (2, 0, 5), (0, 2, 4)]);
+		assert_eq!(calculate_ranks(&defeats_matrix), vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn group_closing_a_cycle_is_dropped_entirely() {
+		// 3 beats everyone, then 0 beats 1 beats 2 beats 0 with equal strength
+		let defeats_matrix = defeats_matrix(
+			4,
+			&[
+				(3, 0, 9),
+				(3, 1, 9),
+				(3, 2, 9),
+				(0, 1, 6),
+				(1, 0, 3),
+				(1, 2, 6),
+				(2, 1, 3),
+				(2, 0, 6),
+				(0, 2, 3),
+			],
+		);
+		assert_eq!(calculate_ranks(&defeats_matrix), vec![1, 1, 1, 0]);
+	}
+
+	#[test]
+	fn locking_stops_at_the_dropped_group() {
+		// the cycle 0 > 1 > 2 > 0 is the strongest group, so the weaker 3 > 0 is not locked either
+		let defeats_matrix = defeats_matrix(
+			4,
+			&[(0, 1, 6), (1, 0, 3), (1, 2, 6), (2, 1, 3), (2, 0, 6), (0, 2, 3), (3, 0, 5), (0, 3, 4)],
+		);
+		assert_eq!(calculate_ranks(&defeats_matrix), vec![0, 0, 0, 0]);
+	}
 }
@@ -0,0 +1,140 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http::HeaderMap;
+
+use crate::errors::FriendVoteError;
+
+/// Shared by the API and the server functions, like the store.
+pub type JoinCodeLimiter = Arc<RateLimiter>;
+
+/// Limits password and passkey logins. A type of its own, so that the server functions find it
+/// in the context next to the [`JoinCodeLimiter`], and logins do not use up join code attempts.
+/// Both count attempts per address given by the [`TrustedProxy`].
+#[derive(Clone)]
+pub struct LoginLimiter(pub Arc<RateLimiter>);
+
+/// The address of the reverse proxy in front of the server, if any, set with `TRUSTED_PROXY`.
+/// Only requests from this address may name the client in `X-Forwarded-For`,
+/// as anyone else could send the header to get a fresh set of attempts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrustedProxy(pub Option<IpAddr>);
+
+impl TrustedProxy {
+	/// The address of the client that sent a request over a connection from `peer`, to rate limit its attempts.
+	pub fn client_address(&self, peer: SocketAddr, headers: &HeaderMap) -> IpAddr {
+		if self.0 != Some(peer.ip()) {
+			return peer.ip();
+		}
+		// the proxy appends the address it received the request from, after any the client sent itself
+		headers
+			.get_all("x-forwarded-for")
+			.iter()
+			.next_back()
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.rsplit(',').next())
+			.and_then(|address| address.trim().parse().ok())
+			.unwrap_or(peer.ip())
+	}
+}
+
+/// Beyond this many tracked clients, the ones whose window has passed are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Allows each client address a number of attempts per time window, to make guessing impractical.
+pub struct RateLimiter {
+	max_attempts: u32,
+	window: Duration,
+	/// Start of the current window and the attempts in it, per client.
+	attempts: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+	pub fn new(max_attempts: u32, window: Duration) -> Self {
+		Self {
+			max_attempts,
+			window,
+			attempts: Mutex::default(),
+		}
+	}
+
+	/// Counts an attempt by `client`, failing with [`FriendVoteError::TooManyRequests`]
+	/// when it already used up the attempts of the current window.
+	pub fn check(&self, client: IpAddr) -> Result<(), FriendVoteError> {
+		let now = Instant::now();
+		let mut attempts = self.attempts.lock().expect("rate limiter lock poisoned");
+		if MAX_TRACKED_CLIENTS <= attempts.len() {
+			attempts.retain(|_, (window_start, _)| now.duration_since(*window_start) < self.window);
+		}
+		let (window_start, n_attempts) = attempts.entry(client).or_insert((now, 0));
+		if self.window <= now.duration_since(*window_start) {
+			*window_start = now;
+			*n_attempts = 0;
+		}
+		if self.max_attempts <= *n_attempts {
+			return Err(FriendVoteError::TooManyRequests("Too many attempts, try again later.".to_string()));
+		}
+		*n_attempts += 1;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn headers(forwarded_for: &[&str]) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		for value in forwarded_for {
+			headers.append("x-forwarded-for", value.parse().unwrap());
+		}
+		headers
+	}
+
+	const PROXY: &str = "10.0.0.1:40000";
+
+	#[test]
+	fn forwarded_addresses_are_only_trusted_from_the_proxy() {
+		let proxy = TrustedProxy(Some("10.0.0.1".parse().unwrap()));
+		let forwarded = headers(&["198.51.100.7"]);
+		assert_eq!(
+			proxy.client_address(PROXY.parse().unwrap(), &forwarded),
+			"198.51.100.7".parse::<IpAddr>().unwrap()
+		);
+		// a client connecting directly cannot pick its address
+		let direct = "203.0.113.5:50000".parse().unwrap();
+		assert_eq!(proxy.client_address(direct, &forwarded), "203.0.113.5".parse::<IpAddr>().unwrap());
+		// and without a trusted proxy, the header is ignored
+		let no_proxy = TrustedProxy::default();
+		assert_eq!(
+			no_proxy.client_address(PROXY.parse().unwrap(), &forwarded),
+			"10.0.0.1".parse::<IpAddr>().unwrap()
+		);
+	}
+
+	#[test]
+	fn the_address_added_by_the_proxy_is_used() {
+		let proxy = TrustedProxy(Some("10.0.0.1".parse().unwrap()));
+		let peer = PROXY.parse().unwrap();
+		// addresses sent by the client come before the one the proxy appends
+		let forwarded = headers(&["192.0.2.1, 198.51.100.7"]);
+		assert_eq!(proxy.client_address(peer, &forwarded), "198.51.100.7".parse::<IpAddr>().unwrap());
+		let forwarded = headers(&["192.0.2.1", "2001:db8::1"]);
+		assert_eq!(proxy.client_address(peer, &forwarded), "2001:db8::1".parse::<IpAddr>().unwrap());
+		// without a usable header, the proxy itself is the client
+		assert_eq!(proxy.client_address(peer, &headers(&[])), "10.0.0.1".parse::<IpAddr>().unwrap());
+		assert_eq!(proxy.client_address(peer, &headers(&["unknown"])), "10.0.0.1".parse::<IpAddr>().unwrap());
+	}
+}
@@ -0,0 +1,879 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{CaseStatement, Expr, OnConflict, SimpleExpr};
+use sea_orm::sqlx::{self, postgres::PgListener};
+use sea_orm::{
+	ActiveModelTrait, ActiveValue, ColumnTrait as _, ConnectionTrait, DatabaseConnection, DbBackend,
+	EntityTrait as _, PaginatorTrait as _, QueryFilter as _, QueryOrder as _, QuerySelect as _, SqlErr,
+	Statement, TransactionTrait as _,
+};
+use tokio::sync::broadcast;
+
+use super::results_cache::{MemoryResultsCache, ResultsCacheMode};
+use super::{
+	already_voted, election_not_found, vote_events_channel, Account, AuditEntry, ElectionInfo, ElectionStore,
+	ElectionTokens, NewElectionRecord, OwnedElection, PairwiseCounts, VoteSummary,
+};
+use crate::ballot::{Ballot, WeightedBallot};
+use crate::endpoints::ElectionResults;
+use crate::entities::prelude::{
+	Account as AccountEntity, AuditEntry as AuditEntryEntity, BallotPattern, Candidate, Election,
+	ElectionVoter, PairwiseCount, Passkey, ResultsCache, Session, Votes,
+};
+use crate::entities::{
+	account, audit_entry, ballot_pattern, candidate, election, election_voter, pairwise_count, passkey,
+	results_cache, session, votes,
+};
+use crate::errors::FriendVoteError;
+
+/// Stores everything in a database through sea-orm.
+pub struct DbStore {
+	db_conn: DatabaseConnection,
+	/// `None` when results are cached in the database.
+	memory_results_cache: Option<MemoryResultsCache>,
+	vote_events: broadcast::Sender<VoteSummary>,
+}
+
+/// Postgres channel on which every ballot is announced with the id of its election,
+/// so that all server instances can publish the new vote count to their subscribers.
+const VOTES_NOTIFY_CHANNEL: &str = "friendvote_votes";
+
+impl DbStore {
+	/// On Postgres, this spawns a task listening for the ballots cast through any server instance,
+	/// so it must be called within a tokio runtime.
+	pub fn new(db_conn: DatabaseConnection, results_cache_mode: ResultsCacheMode) -> Self {
+		let vote_events = vote_events_channel();
+		if db_conn.get_database_backend() == DbBackend::Postgres {
+			tokio::spawn(relay_vote_notifications(db_conn.clone(), vote_events.clone()));
+		}
+		Self {
+			db_conn,
+			memory_results_cache: match results_cache_mode {
+				ResultsCacheMode::Memory => Some(MemoryResultsCache::default()),
+				ResultsCacheMode::Database => None,
+			},
+			vote_events,
+		}
+	}
+
+	async fn get_election_row(&self, election_id: &str) -> Result<election::Model, FriendVoteError> {
+		get_election_row(&self.db_conn, election_id).await
+	}
+}
+
+/// Time to wait before listening for votes again after a database error.
+const LISTEN_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Publishes the vote summary of every election announced on [`VOTES_NOTIFY_CHANNEL`],
+/// including those of the ballots cast through this instance.
+async fn relay_vote_notifications(db_conn: DatabaseConnection, vote_events: broadcast::Sender<VoteSummary>) {
+	let mut listener = loop {
+		match listen_for_votes(&db_conn).await {
+			Ok(listener) => break listener,
+			Err(error) => {
+				tracing::warn!("could not listen for votes, retrying: {}", error);
+				tokio::time::sleep(LISTEN_RETRY_DELAY).await;
+			},
+		}
+	};
+	loop {
+		// the listener reconnects by itself, at the cost of missing the notifications in between
+		let election_id = match listener.recv().await {
+			Ok(notification) => notification.payload().to_string(),
+			Err(error) => {
+				tracing::warn!("lost the vote notifications, retrying: {}", error);
+				// the database may be down, so do not retry immediately
+				tokio::time::sleep(LISTEN_RETRY_DELAY).await;
+				continue;
+			},
+		};
+		match get_election_row(&db_conn, &election_id).await {
+			// there may be nobody listening
+			Ok(election) => {
+				let _ = vote_events.send(vote_summary(&election));
+			},
+			Err(error) => {
+				tracing::warn!("could not read the vote count of election {}: {}", election_id, error)
+			},
+		}
+	}
+}
+
+async fn listen_for_votes(db_conn: &DatabaseConnection) -> Result<PgListener, sqlx::Error> {
+	let mut listener = PgListener::connect_with(db_conn.get_postgres_connection_pool()).await?;
+	listener.listen(VOTES_NOTIFY_CHANNEL).await?;
+	Ok(listener)
+}
+
+fn vote_summary(election: &election::Model) -> VoteSummary {
+	VoteSummary {
+		election_id: election.election_id.clone(),
+		n_votes: election.ballot_count as u64,
+		last_vote_at: election
+			.last_vote_at
+			.map(|last_vote_at| last_vote_at.with_timezone(&Utc)),
+	}
+}
+
+async fn get_election_row(
+	conn: &impl ConnectionTrait,
+	election_id: &str,
+) -> Result<election::Model, FriendVoteError> {
+	Election::find_by_id(election_id)
+		.one(conn)
+		.await?
+		.ok_or_else(|| election_not_found(election_id))
+}
+
+async fn get_n_candidates(conn: &impl ConnectionTrait, election_id: &str) -> Result<usize, FriendVoteError> {
+	let n_candidates = Candidate::find()
+		.filter(candidate::Column::ElectionId.eq(election_id))
+		.count(conn)
+		.await?;
+	Ok(n_candidates as usize)
+}
+
+/// Groups the raw votes by ranking in the database, so memory use grows with the number of distinct rankings
+/// rather than with the number of voters. Rankings are stored in their canonical encoding,
+/// so equal ballots always end up in the same group.
+async fn aggregate_votes(
+	conn: &impl ConnectionTrait,
+	election_id: &str,
+) -> Result<Vec<WeightedBallot>, FriendVoteError> {
+	let groups = Votes::find()
+		.select_only()
+		.column(votes::Column::Ranking)
+		.column_as(votes::Column::VoteId.count(), "n_votes")
+		.filter(votes::Column::ElectionId.eq(election_id))
+		.group_by(votes::Column::Ranking)
+		.into_tuple::<(String, i64)>()
+		.all(conn)
+		.await?;
+	let mut weighted_ballots = groups
+		.into_iter()
+		.map(|(ranking, n_votes)| {
+			Ballot::decode(&ranking)
+				.map(|ballot| WeightedBallot {
+					ballot,
+					weight: n_votes as u64,
+				})
+				.ok_or(FriendVoteError::Internal(format!("Could not decode vote ranking {}", ranking)))
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+	WeightedBallot::sort(&mut weighted_ballots);
+	Ok(weighted_ballots)
+}
+
+async fn get_ballot_patterns(
+	conn: &impl ConnectionTrait,
+	election_id: &str,
+) -> Result<Vec<WeightedBallot>, FriendVoteError> {
+	let patterns = BallotPattern::find()
+		.filter(ballot_pattern::Column::ElectionId.eq(election_id))
+		.all(conn)
+		.await?;
+	let mut weighted_ballots = patterns
+		.into_iter()
+		.map(|pattern| {
+			Ballot::decode(&pattern.ranking)
+				.map(|ballot| WeightedBallot {
+					ballot,
+					weight: pattern.count as u64,
+				})
+				.ok_or(FriendVoteError::Internal(format!(
+					"Could not decode ballot pattern {}",
+					pattern.ranking
+				)))
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+	WeightedBallot::sort(&mut weighted_ballots);
+	Ok(weighted_ballots)
+}
+
+/// Adds `weight` voters to the ballot's pattern, creating it if needed.
+async fn add_ballot_to_patterns(
+	conn: &impl ConnectionTrait,
+	election_id: &str,
+	ballot: &Ballot,
+	weight: i64,
+) -> Result<(), FriendVoteError> {
+	BallotPattern::insert(ballot_pattern::ActiveModel {
+		election_id: ActiveValue::Set(election_id.to_string()),
+		ranking: ActiveValue::Set(ballot.encode()),
+		count: ActiveValue::Set(weight),
+	})
+	.on_conflict(
+		OnConflict::columns([ballot_pattern::Column::ElectionId, ballot_pattern::Column::Ranking])
+			.value(
+				ballot_pattern::Column::Count,
+				Expr::col((BallotPattern, ballot_pattern::Column::Count)).add(weight),
+			)
+			.to_owned(),
+	)
+	.exec(conn)
+	.await?;
+	Ok(())
+}
+
+async fn get_pairwise_counts(
+	conn: &impl ConnectionTrait,
+	election: &election::Model,
+	n_candidates: usize,
+) -> Result<PairwiseCounts, FriendVoteError> {
+	let mut counts = PairwiseCounts::empty(n_candidates);
+	counts.n_votes = election.ballot_count as u64;
+	let rows = PairwiseCount::find()
+		.filter(pairwise_count::Column::ElectionId.eq(&election.election_id))
+		.all(conn)
+		.await?;
+	for row in rows {
+		if let Some(count) = counts
+			.defeats_matrix
+			.get_mut(row.winner as usize)
+			.and_then(|losers| losers.get_mut(row.loser as usize))
+			.filter(|count| count.is_some())
+		{
+			*count = Some(row.count as u64);
+		}
+	}
+	Ok(counts)
+}
+
+/// Writes a row for every ordered pair of different candidates. The election must not have rows yet.
+async fn insert_pairwise_counts(
+	conn: &impl ConnectionTrait,
+	election_id: &str,
+	counts: &PairwiseCounts,
+) -> Result<(), FriendVoteError> {
+	let rows = counts
+		.defeats_matrix
+		.iter()
+		.enumerate()
+		.flat_map(|(winner, losers)| {
+			losers.iter().enumerate().filter_map(move |(loser, count)| {
+				count.map(|count| pairwise_count::ActiveModel {
+					election_id: ActiveValue::Set(election_id.to_string()),
+					winner: ActiveValue::Set(winner as i32),
+					loser: ActiveValue::Set(loser as i32),
+					count: ActiveValue::Set(count as i64),
+				})
+			})
+		})
+		.collect::<Vec<_>>();
+	// stay well below the bind parameter limits of the databases
+	for chunk in rows.chunks(500) {
+		PairwiseCount::insert_many(chunk.iter().cloned())
+			.exec(conn)
+			.await?;
+	}
+	Ok(())
+}
+
+/// Maps each candidate on the ballot to the index of its group, so lower means more preferred.
+fn group_index_expr(column: pairwise_count::Column, ballot: &Ballot) -> SimpleExpr {
+	let mut case = CaseStatement::new();
+	for (group_ind, group) in ballot.groups().iter().enumerate() {
+		case = case.case(column.is_in(group.iter().map(|candidate| *candidate as i32)), group_ind as i32);
+	}
+	case.into()
+}
+
+/// Adds `weight` to the count of every pair the ballot ranks and to the ballot count,
+/// and invalidates the cached results.
+///
+/// The pairs are updated in a single statement, so a vote costs the same number of round trips
+/// regardless of the number of candidates.
+async fn add_ballot_to_counts(
+	conn: &impl ConnectionTrait,
+	election_id: &str,
+	ballot: &Ballot,
+	weight: i64,
+) -> Result<(), FriendVoteError> {
+	if ballot.groups().len() > 1 {
+		PairwiseCount::update_many()
+			.col_expr(pairwise_count::Column::Count, Expr::col(pairwise_count::Column::Count).add(weight))
+			.filter(pairwise_count::Column::ElectionId.eq(election_id))
+			.filter(
+				Expr::expr(group_index_expr(pairwise_count::Column::Winner, ballot))
+					.lt(group_index_expr(pairwise_count::Column::Loser, ballot)),
+			)
+			.exec(conn)
+			.await?;
+	}
+	Election::update_many()
+		.col_expr(election::Column::BallotCount, Expr::col(election::Column::BallotCount).add(weight))
+		.col_expr(election::Column::ResultsVersion, Expr::col(election::Column::ResultsVersion).add(1))
+		.filter(election::Column::ElectionId.eq(election_id))
+		.exec(conn)
+		.await?;
+	Ok(())
+}
+
+#[async_trait::async_trait]
+impl ElectionStore for DbStore {
+	async fn insert_election(&self, election: NewElectionRecord) -> Result<(), FriendVoteError> {
+		let txn = self.db_conn.begin().await?;
+		election::ActiveModel {
+			election_id: ActiveValue::Set(election.election_id.clone()),
+			name: ActiveValue::Set(election.name),
+			admin_id: ActiveValue::Set(election.tokens.admin_id),
+			result_id: ActiveValue::Set(election.tokens.result_id),
+			voting_open: ActiveValue::Set(true),
+			ballot_count: ActiveValue::Set(0),
+			results_version: ActiveValue::Set(0),
+			last_vote_at: ActiveValue::Set(None),
+			join_code: ActiveValue::Set(None),
+			shuffle_candidates: ActiveValue::Set(election.shuffle_candidates),
+			review_ballots: ActiveValue::Set(election.review_ballots),
+			owner_account_id: ActiveValue::Set(election.owner_account_id),
+			members_only: ActiveValue::Set(election.members_only),
+		}
+		.insert(&txn)
+		.await?;
+		let counts = PairwiseCounts::empty(election.candidates.len());
+		insert_pairwise_counts(&txn, &election.election_id, &counts).await?;
+		Candidate::insert_many(
+			election
+				.candidates
+				.into_iter()
+				.enumerate()
+				.map(|(position, name)| candidate::ActiveModel {
+					election_id: ActiveValue::Set(election.election_id.clone()),
+					position: ActiveValue::Set(position as i32),
+					name: ActiveValue::Set(name),
+				}),
+		)
+		.exec(&txn)
+		.await?;
+		txn.commit().await?;
+		Ok(())
+	}
+
+	async fn get_election(&self, election_id: &str) -> Result<ElectionInfo, FriendVoteError> {
+		let db_row = self.get_election_row(election_id).await?;
+		let candidates = Candidate::find()
+			.filter(candidate::Column::ElectionId.eq(election_id))
+			.order_by_asc(candidate::Column::Position)
+			.all(&self.db_conn)
+			.await?;
+		Ok(ElectionInfo {
+			election_id: db_row.election_id,
+			name: db_row.name,
+			candidates: candidates.into_iter().map(|candidate| candidate.name).collect(),
+			voting_open: db_row.voting_open,
+			results_version: db_row.results_version as u64,
+			join_code: db_row.join_code,
+			shuffle_candidates: db_row.shuffle_candidates,
+			review_ballots: db_row.review_ballots,
+			owner_account_id: db_row.owner_account_id,
+			members_only: db_row.members_only,
+		})
+	}
+
+	async fn set_voting_open(&self, election_id: &str, voting_open: bool) -> Result<(), FriendVoteError> {
+		let mut update = Election::update_many()
+			.col_expr(election::Column::VotingOpen, Expr::value(voting_open))
+			.col_expr(election::Column::ResultsVersion, Expr::col(election::Column::ResultsVersion).add(1));
+		if !voting_open {
+			update = update.col_expr(election::Column::JoinCode, Expr::value(Option::<String>::None));
+		}
+		let result = update
+			.filter(election::Column::ElectionId.eq(election_id))
+			.exec(&self.db_conn)
+			.await?;
+		if result.rows_affected == 0 {
+			return Err(election_not_found(election_id));
+		}
+		Ok(())
+	}
+
+	async fn set_join_code(&self, election_id: &str, join_code: &str) -> Result<bool, FriendVoteError> {
+		let result = Election::update_many()
+			.col_expr(election::Column::JoinCode, Expr::value(join_code))
+			.filter(election::Column::ElectionId.eq(election_id))
+			.exec(&self.db_conn)
+			.await;
+		match result {
+			Ok(result) if result.rows_affected == 0 => Err(election_not_found(election_id)),
+			Ok(_) => Ok(true),
+			// the unique index settles races between elections asking for the same code
+			Err(error) if matches!(error.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => Ok(false),
+			Err(error) => Err(error.into()),
+		}
+	}
+
+	async fn find_election_by_join_code(&self, join_code: &str) -> Result<Option<String>, FriendVoteError> {
+		let election = Election::find()
+			.filter(election::Column::JoinCode.eq(join_code))
+			.one(&self.db_conn)
+			.await?;
+		Ok(election.map(|election| election.election_id))
+	}
+
+	async fn get_tokens(&self, election_id: &str) -> Result<ElectionTokens, FriendVoteError> {
+		let db_row = self.get_election_row(election_id).await?;
+		Ok(ElectionTokens {
+			admin_id: db_row.admin_id,
+			result_id: db_row.result_id,
+		})
+	}
+
+	async fn insert_ballot(
+		&self,
+		election_id: &str,
+		ballot: &Ballot,
+		voter: Option<&str>,
+	) -> Result<(), FriendVoteError> {
+		let txn = self.db_conn.begin().await?;
+		if let Some(voter) = voter {
+			let result = election_voter::ActiveModel {
+				election_id: ActiveValue::Set(election_id.to_string()),
+				account_id: ActiveValue::Set(voter.to_string()),
+			}
+			.insert(&txn)
+			.await;
+			match result {
+				Ok(_) => {},
+				// the primary key settles races between two ballots of the same voter
+				Err(error) if matches!(error.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+					return Err(already_voted());
+				},
+				Err(error) => return Err(error.into()),
+			}
+		}
+		votes::ActiveModel {
+			vote_id: ActiveValue::NotSet,
+			election_id: ActiveValue::Set(election_id.to_string()),
+			ranking: ActiveValue::Set(ballot.encode()),
+		}
+		.insert(&txn)
+		.await?;
+		add_ballot_to_counts(&txn, election_id, ballot, 1).await?;
+		add_ballot_to_patterns(&txn, election_id, ballot, 1).await?;
+		let election = election::ActiveModel {
+			election_id: ActiveValue::Unchanged(election_id.to_string()),
+			last_vote_at: ActiveValue::Set(Some(Utc::now().into())),
+			..Default::default()
+		}
+		.update(&txn)
+		.await?;
+		if txn.get_database_backend() == DbBackend::Postgres {
+			// delivered on commit to every instance, this one included, see `relay_vote_notifications`
+			txn.execute(Statement::from_sql_and_values(
+				DbBackend::Postgres,
+				"SELECT pg_notify($1, $2)",
+				[VOTES_NOTIFY_CHANNEL.into(), election_id.into()],
+			))
+			.await?;
+			txn.commit().await?;
+		} else {
+			let summary = vote_summary(&election);
+			txn.commit().await?;
+			// there may be nobody listening
+			let _ = self.vote_events.send(summary);
+		}
+		Ok(())
+	}
+
+	async fn has_voted(&self, election_id: &str, account_id: &str) -> Result<bool, FriendVoteError> {
+		let voter = ElectionVoter::find_by_id((election_id.to_string(), account_id.to_string()))
+			.one(&self.db_conn)
+			.await?;
+		Ok(voter.is_some())
+	}
+
+	async fn get_vote_summary(&self, election_id: &str) -> Result<VoteSummary, FriendVoteError> {
+		Ok(vote_summary(&self.get_election_row(election_id).await?))
+	}
+
+	fn subscribe_votes(&self) -> broadcast::Receiver<VoteSummary> {
+		self.vote_events.subscribe()
+	}
+
+	async fn get_ballot_patterns(&self, election_id: &str) -> Result<Vec<WeightedBallot>, FriendVoteError> {
+		get_ballot_patterns(&self.db_conn, election_id).await
+	}
+
+	async fn get_pairwise_counts(&self, election_id: &str) -> Result<PairwiseCounts, FriendVoteError> {
+		let election = self.get_election_row(election_id).await?;
+		let n_candidates = get_n_candidates(&self.db_conn, election_id).await?;
+		get_pairwise_counts(&self.db_conn, &election, n_candidates).await
+	}
+
+	async fn rebuild_tallies(&self, election_id: &str) -> Result<bool, FriendVoteError> {
+		let txn = self.db_conn.begin().await?;
+		let election = get_election_row(&txn, election_id).await?;
+		let n_candidates = get_n_candidates(&txn, election_id).await?;
+		let stored_counts = get_pairwise_counts(&txn, &election, n_candidates).await?;
+		let stored_patterns = get_ballot_patterns(&txn, election_id).await?;
+		let patterns = aggregate_votes(&txn, election_id).await?;
+		let counts = PairwiseCounts::from_ballots(n_candidates, &patterns);
+		if stored_counts == counts && stored_patterns == patterns {
+			return Ok(false);
+		}
+		PairwiseCount::delete_many()
+			.filter(pairwise_count::Column::ElectionId.eq(election_id))
+			.exec(&txn)
+			.await?;
+		insert_pairwise_counts(&txn, election_id, &counts).await?;
+		election::ActiveModel {
+			election_id: ActiveValue::Unchanged(election_id.to_string()),
+			ballot_count: ActiveValue::Set(counts.n_votes as i64),
+			results_version: ActiveValue::Set(election.results_version + 1),
+			..Default::default()
+		}
+		.update(&txn)
+		.await?;
+		BallotPattern::delete_many()
+			.filter(ballot_pattern::Column::ElectionId.eq(election_id))
+			.exec(&txn)
+			.await?;
+		for pattern in patterns.iter() {
+			add_ballot_to_patterns(&txn, election_id, &pattern.ballot, pattern.weight as i64).await?;
+		}
+		txn.commit().await?;
+		Ok(true)
+	}
+
+	async fn get_election_ids(&self) -> Result<Vec<String>, FriendVoteError> {
+		Ok(Election::find()
+			.select_only()
+			.column(election::Column::ElectionId)
+			.into_tuple::<String>()
+			.all(&self.db_conn)
+			.await?)
+	}
+
+	async fn insert_audit_entry(&self, election_id: &str, action: &str) -> Result<(), FriendVoteError> {
+		audit_entry::ActiveModel {
+			audit_entry_id: ActiveValue::NotSet,
+			election_id: ActiveValue::Set(election_id.to_string()),
+			created_at: ActiveValue::Set(Utc::now().into()),
+			action: ActiveValue::Set(action.to_string()),
+		}
+		.insert(&self.db_conn)
+		.await?;
+		Ok(())
+	}
+
+	async fn get_audit_entries(&self, election_id: &str) -> Result<Vec<AuditEntry>, FriendVoteError> {
+		let entries = AuditEntryEntity::find()
+			.filter(audit_entry::Column::ElectionId.eq(election_id))
+			.order_by_asc(audit_entry::Column::AuditEntryId)
+			.all(&self.db_conn)
+			.await?;
+		Ok(entries
+			.into_iter()
+			.map(|entry| AuditEntry {
+				created_at: entry.created_at.with_timezone(&Utc),
+				action: entry.action,
+			})
+			.collect())
+	}
+
+	async fn get_cached_results(
+		&self,
+		election_id: &str,
+		configuration: &str,
+		results_version: u64,
+	) -> Result<Option<ElectionResults>, FriendVoteError> {
+		if let Some(cache) = self.memory_results_cache.as_ref() {
+			return Ok(cache.get(election_id, configuration, results_version));
+		}
+		let cached = ResultsCache::find_by_id((election_id.to_string(), configuration.to_string()))
+			.filter(results_cache::Column::ResultsVersion.eq(results_version as i64))
+			.one(&self.db_conn)
+			.await?;
+		// results cached by an older server version may not deserialize anymore, in which case they are recomputed
+		Ok(cached.and_then(|cached| serde_json::from_str(&cached.results).ok()))
+	}
+
+	async fn cache_results(
+		&self,
+		election_id: &str,
+		configuration: &str,
+		results_version: u64,
+		results: &ElectionResults,
+	) -> Result<(), FriendVoteError> {
+		if let Some(cache) = self.memory_results_cache.as_ref() {
+			cache.put(election_id, configuration, results_version, results);
+			return Ok(());
+		}
+		let results = serde_json::to_string(results)
+			.map_err(|error| FriendVoteError::Internal(format!("Could not serialize results: {}", error)))?;
+		ResultsCache::insert(results_cache::ActiveModel {
+			election_id: ActiveValue::Set(election_id.to_string()),
+			configuration: ActiveValue::Set(configuration.to_string()),
+			results_version: ActiveValue::Set(results_version as i64),
+			results: ActiveValue::Set(results),
+		})
+		.on_conflict(
+			// never replace results of a newer version by those of an older one
+			OnConflict::columns([results_cache::Column::ElectionId, results_cache::Column::Configuration])
+				.update_columns([results_cache::Column::ResultsVersion, results_cache::Column::Results])
+				.action_and_where(
+					Expr::col((ResultsCache, results_cache::Column::ResultsVersion))
+						.lte(results_version as i64),
+				)
+				.to_owned(),
+		)
+		.exec_without_returning(&self.db_conn)
+		.await?;
+		Ok(())
+	}
+
+	async fn insert_account(&self, account: Account) -> Result<bool, FriendVoteError> {
+		let result = account::ActiveModel {
+			account_id: ActiveValue::Set(account.account_id),
+			username: ActiveValue::Set(account.username),
+			password_hash: ActiveValue::Set(account.password_hash),
+			created_at: ActiveValue::Set(Utc::now().into()),
+			oidc_subject: ActiveValue::Set(account.oidc_subject),
+			oidc_member: ActiveValue::Set(account.oidc_member),
+		}
+		.insert(&self.db_conn)
+		.await;
+		match result {
+			Ok(_) => Ok(true),
+			// the unique indexes settle races between sign ups with the same username or subject
+			Err(error) if matches!(error.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => Ok(false),
+			Err(error) => Err(error.into()),
+		}
+	}
+
+	async fn get_account(&self, account_id: &str) -> Result<Account, FriendVoteError> {
+		AccountEntity::find_by_id(account_id)
+			.one(&self.db_conn)
+			.await?
+			.map(account_from_row)
+			.ok_or_else(|| FriendVoteError::NotFound(format!("Could not find account with id {}", account_id)))
+	}
+
+	async fn find_account_by_username(&self, username: &str) -> Result<Option<Account>, FriendVoteError> {
+		Ok(AccountEntity::find()
+			.filter(account::Column::Username.eq(username))
+			.one(&self.db_conn)
+			.await?
+			.map(account_from_row))
+	}
+
+	async fn find_account_by_oidc_subject(&self, subject: &str) -> Result<Option<Account>, FriendVoteError> {
+		Ok(AccountEntity::find()
+			.filter(account::Column::OidcSubject.eq(subject))
+			.one(&self.db_conn)
+			.await?
+			.map(account_from_row))
+	}
+
+	async fn set_oidc_member(&self, account_id: &str, member: bool) -> Result<(), FriendVoteError> {
+		let result = AccountEntity::update_many()
+			.col_expr(account::Column::OidcMember, Expr::value(member))
+			.filter(account::Column::AccountId.eq(account_id))
+			.exec(&self.db_conn)
+			.await?;
+		if result.rows_affected == 0 {
+			return Err(FriendVoteError::NotFound(format!("Could not find account with id {}", account_id)));
+		}
+		Ok(())
+	}
+
+	async fn get_owned_elections(&self, account_id: &str) -> Result<Vec<OwnedElection>, FriendVoteError> {
+		let elections = Election::find()
+			.filter(election::Column::OwnerAccountId.eq(account_id))
+			.order_by_asc(election::Column::Name)
+			.all(&self.db_conn)
+			.await?;
+		Ok(elections
+			.into_iter()
+			.map(|election| OwnedElection {
+				election_id: election.election_id,
+				name: election.name,
+			})
+			.collect())
+	}
+
+	async fn insert_passkey(
+		&self,
+		account_id: &str,
+		credential_id: &str,
+		passkey: &str,
+	) -> Result<(), FriendVoteError> {
+		let result = passkey::ActiveModel {
+			credential_id: ActiveValue::Set(credential_id.to_string()),
+			account_id: ActiveValue::Set(account_id.to_string()),
+			passkey: ActiveValue::Set(passkey.to_string()),
+		}
+		.insert(&self.db_conn)
+		.await;
+		match result {
+			Ok(_) => Ok(()),
+			Err(error) if matches!(error.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+				Err(FriendVoteError::Validation("This passkey is already registered.".to_string()))
+			},
+			Err(error) => Err(error.into()),
+		}
+	}
+
+	async fn get_passkeys(&self, account_id: &str) -> Result<Vec<String>, FriendVoteError> {
+		Ok(Passkey::find()
+			.filter(passkey::Column::AccountId.eq(account_id))
+			.select_only()
+			.column(passkey::Column::Passkey)
+			.into_tuple::<String>()
+			.all(&self.db_conn)
+			.await?)
+	}
+
+	async fn update_passkey(&self, credential_id: &str, passkey: &str) -> Result<(), FriendVoteError> {
+		Passkey::update_many()
+			.col_expr(passkey::Column::Passkey, Expr::value(passkey))
+			.filter(passkey::Column::CredentialId.eq(credential_id))
+			.exec(&self.db_conn)
+			.await?;
+		Ok(())
+	}
+
+	async fn insert_session(
+		&self,
+		session_id: &str,
+		account_id: &str,
+		expires_at: DateTime<Utc>,
+	) -> Result<(), FriendVoteError> {
+		let txn = self.db_conn.begin().await?;
+		Session::delete_many()
+			.filter(session::Column::ExpiresAt.lte(Utc::now()))
+			.exec(&txn)
+			.await?;
+		session::ActiveModel {
+			session_id: ActiveValue::Set(session_id.to_string()),
+			account_id: ActiveValue::Set(account_id.to_string()),
+			expires_at: ActiveValue::Set(expires_at.into()),
+		}
+		.insert(&txn)
+		.await?;
+		txn.commit().await?;
+		Ok(())
+	}
+
+	async fn get_session_account(&self, session_id: &str) -> Result<Option<String>, FriendVoteError> {
+		Ok(Session::find_by_id(session_id)
+			.filter(session::Column::ExpiresAt.gt(Utc::now()))
+			.one(&self.db_conn)
+			.await?
+			.map(|session| session.account_id))
+	}
+
+	async fn delete_session(&self, session_id: &str) -> Result<(), FriendVoteError> {
+		Session::delete_by_id(session_id).exec(&self.db_conn).await?;
+		Ok(())
+	}
+}
+
+fn account_from_row(row: account::Model) -> Account {
+	Account {
+		account_id: row.account_id,
+		username: row.username,
+		password_hash: row.password_hash,
+		oidc_subject: row.oidc_subject,
+		oidc_member: row.oidc_member,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use migration::{Migrator, MigratorTrait as _};
+	use sea_orm::{ConnectOptions, Database};
+
+	use super::*;
+	use crate::store::tests::random_votes;
+
+	const ELECTION_ID: &str = "election";
+	const N_CANDIDATES: usize = 6;
+
+	/// A store on a fresh in-memory SQLite database, with one election.
+	async fn store_with_election() -> DbStore {
+		// every connection would get its own in-memory database
+		let mut db_options = ConnectOptions::new("sqlite::memory:");
+		db_options.max_connections(1).sqlx_logging(false);
+		let db_conn = Database::connect(db_options).await.unwrap();
+		Migrator::up(&db_conn, None).await.unwrap();
+		let store = DbStore::new(db_conn, ResultsCacheMode::Memory);
+		store
+			.insert_election(NewElectionRecord {
+				election_id: ELECTION_ID.to_string(),
+				name: "Lunch".to_string(),
+				candidates: (0..N_CANDIDATES)
+					.map(|ind| format!("Candidate {}", ind))
+					.collect(),
+				shuffle_candidates: false,
+				review_ballots: false,
+				tokens: ElectionTokens {
+					admin_id: "admin".to_string(),
+					result_id: "result".to_string(),
+				},
+				owner_account_id: None,
+				members_only: false,
+			})
+			.await
+			.unwrap();
+		store
+	}
+
+	async fn insert_random_ballots(store: &DbStore) -> Vec<Ballot> {
+		let ballots = random_votes(N_CANDIDATES, 200)
+			.iter()
+			.map(|(cand_order, comp_is_bigger)| Ballot::from_order(cand_order, comp_is_bigger))
+			.collect::<Vec<_>>();
+		for ballot in ballots.iter() {
+			store.insert_ballot(ELECTION_ID, ballot, None).await.unwrap();
+		}
+		ballots
+	}
+
+	#[tokio::test]
+	async fn sql_aggregation_matches_incremental_tally() {
+		let store = store_with_election().await;
+		let ballots = insert_random_ballots(&store).await;
+
+		let patterns = WeightedBallot::aggregate(&ballots);
+		assert_eq!(aggregate_votes(&store.db_conn, ELECTION_ID).await.unwrap(), patterns);
+		assert_eq!(store.get_ballot_patterns(ELECTION_ID).await.unwrap(), patterns);
+		assert_eq!(
+			store.get_pairwise_counts(ELECTION_ID).await.unwrap(),
+			PairwiseCounts::from_ballots(N_CANDIDATES, &patterns)
+		);
+		assert!(!store.rebuild_tallies(ELECTION_ID).await.unwrap());
+	}
+
+	#[tokio::test]
+	async fn rebuilding_repairs_the_tally() {
+		let store = store_with_election().await;
+		let ballots = insert_random_ballots(&store).await;
+		PairwiseCount::delete_many()
+			.filter(pairwise_count::Column::ElectionId.eq(ELECTION_ID))
+			.exec(&store.db_conn)
+			.await
+			.unwrap();
+		BallotPattern::delete_many().exec(&store.db_conn).await.unwrap();
+
+		assert!(store.rebuild_tallies(ELECTION_ID).await.unwrap());
+		let patterns = WeightedBallot::aggregate(&ballots);
+		assert_eq!(store.get_ballot_patterns(ELECTION_ID).await.unwrap(), patterns);
+		assert_eq!(
+			store.get_pairwise_counts(ELECTION_ID).await.unwrap(),
+			PairwiseCounts::from_ballots(N_CANDIDATES, &patterns)
+		);
+		assert!(!store.rebuild_tallies(ELECTION_ID).await.unwrap());
+	}
+}
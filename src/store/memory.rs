@@ -0,0 +1,421 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+
+use super::results_cache::MemoryResultsCache;
+use super::{
+	already_voted, election_not_found, vote_events_channel, Account, AuditEntry, ElectionInfo, ElectionStore,
+	ElectionTokens, NewElectionRecord, OwnedElection, PairwiseCounts, VoteSummary,
+};
+use crate::ballot::{Ballot, WeightedBallot};
+use crate::endpoints::ElectionResults;
+use crate::errors::FriendVoteError;
+
+struct MemoryElection {
+	info: ElectionInfo,
+	tokens: ElectionTokens,
+	ballots: Vec<Ballot>,
+	counts: PairwiseCounts,
+	patterns: HashMap<Ballot, u64>,
+	last_vote_at: Option<DateTime<Utc>>,
+	audit_entries: Vec<AuditEntry>,
+	/// The accounts that voted, in members-only elections.
+	voters: HashSet<String>,
+}
+
+impl MemoryElection {
+	fn vote_summary(&self) -> VoteSummary {
+		VoteSummary {
+			election_id: self.info.election_id.clone(),
+			n_votes: self.counts.n_votes,
+			last_vote_at: self.last_vote_at,
+		}
+	}
+}
+
+struct MemoryPasskey {
+	account_id: String,
+	passkey: String,
+}
+
+#[derive(Default)]
+struct MemoryAccounts {
+	accounts: HashMap<String, Account>,
+	/// Keyed by credential id.
+	passkeys: HashMap<String, MemoryPasskey>,
+	/// The account and expiry of each session.
+	sessions: HashMap<String, (String, DateTime<Utc>)>,
+}
+
+/// Keeps everything in memory, for tests and throwaway instances. All data is lost when the server stops.
+pub struct MemoryStore {
+	elections: Mutex<HashMap<String, MemoryElection>>,
+	accounts: Mutex<MemoryAccounts>,
+	results_cache: MemoryResultsCache,
+	vote_events: broadcast::Sender<VoteSummary>,
+}
+
+impl Default for MemoryStore {
+	fn default() -> Self {
+		Self {
+			elections: Mutex::default(),
+			accounts: Mutex::default(),
+			results_cache: MemoryResultsCache::default(),
+			vote_events: vote_events_channel(),
+		}
+	}
+}
+
+impl MemoryStore {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	fn with_election<T>(
+		&self,
+		election_id: &str,
+		f: impl FnOnce(&mut MemoryElection) -> T,
+	) -> Result<T, FriendVoteError> {
+		let mut elections = self.elections.lock().expect("memory store lock poisoned");
+		elections
+			.get_mut(election_id)
+			.map(f)
+			.ok_or_else(|| election_not_found(election_id))
+	}
+}
+
+#[async_trait::async_trait]
+impl ElectionStore for MemoryStore {
+	async fn insert_election(&self, election: NewElectionRecord) -> Result<(), FriendVoteError> {
+		let mut elections = self.elections.lock().expect("memory store lock poisoned");
+		if elections.contains_key(&election.election_id) {
+			return Err(FriendVoteError::Internal(format!(
+				"Election with id {} already exists",
+				election.election_id
+			)));
+		}
+		let counts = PairwiseCounts::empty(election.candidates.len());
+		elections.insert(
+			election.election_id.clone(),
+			MemoryElection {
+				info: ElectionInfo {
+					election_id: election.election_id,
+					name: election.name,
+					candidates: election.candidates,
+					voting_open: true,
+					results_version: 0,
+					join_code: None,
+					shuffle_candidates: election.shuffle_candidates,
+					review_ballots: election.review_ballots,
+					owner_account_id: election.owner_account_id,
+					members_only: election.members_only,
+				},
+				tokens: election.tokens,
+				ballots: Vec::new(),
+				counts,
+				patterns: HashMap::new(),
+				last_vote_at: None,
+				audit_entries: Vec::new(),
+				voters: HashSet::new(),
+			},
+		);
+		Ok(())
+	}
+
+	async fn get_election(&self, election_id: &str) -> Result<ElectionInfo, FriendVoteError> {
+		self.with_election(election_id, |election| election.info.clone())
+	}
+
+	async fn set_voting_open(&self, election_id: &str, voting_open: bool) -> Result<(), FriendVoteError> {
+		self.with_election(election_id, |election| {
+			election.info.voting_open = voting_open;
+			election.info.results_version += 1;
+			if !voting_open {
+				election.info.join_code = None;
+			}
+		})
+	}
+
+	async fn set_join_code(&self, election_id: &str, join_code: &str) -> Result<bool, FriendVoteError> {
+		let mut elections = self.elections.lock().expect("memory store lock poisoned");
+		if elections
+			.values()
+			.any(|election| election.info.join_code.as_deref() == Some(join_code))
+		{
+			return Ok(false);
+		}
+		let election = elections
+			.get_mut(election_id)
+			.ok_or_else(|| election_not_found(election_id))?;
+		election.info.join_code = Some(join_code.to_string());
+		Ok(true)
+	}
+
+	async fn find_election_by_join_code(&self, join_code: &str) -> Result<Option<String>, FriendVoteError> {
+		let elections = self.elections.lock().expect("memory store lock poisoned");
+		Ok(elections
+			.values()
+			.find(|election| election.info.join_code.as_deref() == Some(join_code))
+			.map(|election| election.info.election_id.clone()))
+	}
+
+	async fn get_tokens(&self, election_id: &str) -> Result<ElectionTokens, FriendVoteError> {
+		self.with_election(election_id, |election| election.tokens.clone())
+	}
+
+	async fn insert_ballot(
+		&self,
+		election_id: &str,
+		ballot: &Ballot,
+		voter: Option<&str>,
+	) -> Result<(), FriendVoteError> {
+		let summary = self.with_election(election_id, |election| {
+			if let Some(voter) = voter {
+				if !election.voters.insert(voter.to_string()) {
+					return Err(already_voted());
+				}
+			}
+			election.counts.add_ballot(ballot, 1);
+			*election.patterns.entry(ballot.clone()).or_default() += 1;
+			election.ballots.push(ballot.clone());
+			election.info.results_version += 1;
+			election.last_vote_at = Some(Utc::now());
+			Ok(election.vote_summary())
+		})??;
+		// there may be nobody listening
+		let _ = self.vote_events.send(summary);
+		Ok(())
+	}
+
+	async fn has_voted(&self, election_id: &str, account_id: &str) -> Result<bool, FriendVoteError> {
+		self.with_election(election_id, |election| election.voters.contains(account_id))
+	}
+
+	async fn get_vote_summary(&self, election_id: &str) -> Result<VoteSummary, FriendVoteError> {
+		self.with_election(election_id, |election| election.vote_summary())
+	}
+
+	fn subscribe_votes(&self) -> broadcast::Receiver<VoteSummary> {
+		self.vote_events.subscribe()
+	}
+
+	async fn get_ballot_patterns(&self, election_id: &str) -> Result<Vec<WeightedBallot>, FriendVoteError> {
+		self.with_election(election_id, |election| {
+			let mut patterns = election
+				.patterns
+				.iter()
+				.map(|(ballot, weight)| WeightedBallot {
+					ballot: ballot.clone(),
+					weight: *weight,
+				})
+				.collect::<Vec<_>>();
+			WeightedBallot::sort(&mut patterns);
+			patterns
+		})
+	}
+
+	async fn get_pairwise_counts(&self, election_id: &str) -> Result<PairwiseCounts, FriendVoteError> {
+		self.with_election(election_id, |election| election.counts.clone())
+	}
+
+	async fn rebuild_tallies(&self, election_id: &str) -> Result<bool, FriendVoteError> {
+		self.with_election(election_id, |election| {
+			let weighted_ballots = WeightedBallot::aggregate(&election.ballots);
+			let counts = PairwiseCounts::from_ballots(election.info.candidates.len(), &weighted_ballots);
+			let patterns = weighted_ballots
+				.into_iter()
+				.map(|weighted| (weighted.ballot, weighted.weight))
+				.collect::<HashMap<_, _>>();
+			let was_wrong = counts != election.counts || patterns != election.patterns;
+			election.counts = counts;
+			election.patterns = patterns;
+			if was_wrong {
+				election.info.results_version += 1;
+			}
+			was_wrong
+		})
+	}
+
+	async fn get_election_ids(&self) -> Result<Vec<String>, FriendVoteError> {
+		let elections = self.elections.lock().expect("memory store lock poisoned");
+		Ok(elections.keys().cloned().collect())
+	}
+
+	async fn insert_audit_entry(&self, election_id: &str, action: &str) -> Result<(), FriendVoteError> {
+		self.with_election(election_id, |election| {
+			election.audit_entries.push(AuditEntry {
+				created_at: Utc::now(),
+				action: action.to_string(),
+			})
+		})
+	}
+
+	async fn get_audit_entries(&self, election_id: &str) -> Result<Vec<AuditEntry>, FriendVoteError> {
+		self.with_election(election_id, |election| election.audit_entries.clone())
+	}
+
+	async fn get_cached_results(
+		&self,
+		election_id: &str,
+		configuration: &str,
+		results_version: u64,
+	) -> Result<Option<ElectionResults>, FriendVoteError> {
+		Ok(self
+			.results_cache
+			.get(election_id, configuration, results_version))
+	}
+
+	async fn cache_results(
+		&self,
+		election_id: &str,
+		configuration: &str,
+		results_version: u64,
+		results: &ElectionResults,
+	) -> Result<(), FriendVoteError> {
+		self.results_cache
+			.put(election_id, configuration, results_version, results);
+		Ok(())
+	}
+
+	async fn insert_account(&self, account: Account) -> Result<bool, FriendVoteError> {
+		let mut accounts = self.accounts.lock().expect("memory store lock poisoned");
+		if accounts.accounts.values().any(|existing| {
+			existing.username == account.username
+				|| (account.oidc_subject.is_some() && existing.oidc_subject == account.oidc_subject)
+		}) {
+			return Ok(false);
+		}
+		accounts.accounts.insert(account.account_id.clone(), account);
+		Ok(true)
+	}
+
+	async fn get_account(&self, account_id: &str) -> Result<Account, FriendVoteError> {
+		let accounts = self.accounts.lock().expect("memory store lock poisoned");
+		accounts
+			.accounts
+			.get(account_id)
+			.cloned()
+			.ok_or_else(|| FriendVoteError::NotFound(format!("Could not find account with id {}", account_id)))
+	}
+
+	async fn find_account_by_username(&self, username: &str) -> Result<Option<Account>, FriendVoteError> {
+		let accounts = self.accounts.lock().expect("memory store lock poisoned");
+		Ok(accounts
+			.accounts
+			.values()
+			.find(|account| account.username == username)
+			.cloned())
+	}
+
+	async fn find_account_by_oidc_subject(&self, subject: &str) -> Result<Option<Account>, FriendVoteError> {
+		let accounts = self.accounts.lock().expect("memory store lock poisoned");
+		Ok(accounts
+			.accounts
+			.values()
+			.find(|account| account.oidc_subject.as_deref() == Some(subject))
+			.cloned())
+	}
+
+	async fn set_oidc_member(&self, account_id: &str, member: bool) -> Result<(), FriendVoteError> {
+		let mut accounts = self.accounts.lock().expect("memory store lock poisoned");
+		let account = accounts.accounts.get_mut(account_id).ok_or_else(|| {
+			FriendVoteError::NotFound(format!("Could not find account with id {}", account_id))
+		})?;
+		account.oidc_member = member;
+		Ok(())
+	}
+
+	async fn get_owned_elections(&self, account_id: &str) -> Result<Vec<OwnedElection>, FriendVoteError> {
+		let elections = self.elections.lock().expect("memory store lock poisoned");
+		let mut owned = elections
+			.values()
+			.filter(|election| election.info.owner_account_id.as_deref() == Some(account_id))
+			.map(|election| OwnedElection {
+				election_id: election.info.election_id.clone(),
+				name: election.info.name.clone(),
+			})
+			.collect::<Vec<_>>();
+		owned.sort_by(|a, b| a.name.cmp(&b.name));
+		Ok(owned)
+	}
+
+	async fn insert_passkey(
+		&self,
+		account_id: &str,
+		credential_id: &str,
+		passkey: &str,
+	) -> Result<(), FriendVoteError> {
+		let mut accounts = self.accounts.lock().expect("memory store lock poisoned");
+		if accounts.passkeys.contains_key(credential_id) {
+			return Err(FriendVoteError::Validation("This passkey is already registered.".to_string()));
+		}
+		accounts.passkeys.insert(
+			credential_id.to_string(),
+			MemoryPasskey {
+				account_id: account_id.to_string(),
+				passkey: passkey.to_string(),
+			},
+		);
+		Ok(())
+	}
+
+	async fn get_passkeys(&self, account_id: &str) -> Result<Vec<String>, FriendVoteError> {
+		let accounts = self.accounts.lock().expect("memory store lock poisoned");
+		Ok(accounts
+			.passkeys
+			.values()
+			.filter(|passkey| passkey.account_id == account_id)
+			.map(|passkey| passkey.passkey.clone())
+			.collect())
+	}
+
+	async fn update_passkey(&self, credential_id: &str, passkey: &str) -> Result<(), FriendVoteError> {
+		let mut accounts = self.accounts.lock().expect("memory store lock poisoned");
+		if let Some(stored) = accounts.passkeys.get_mut(credential_id) {
+			stored.passkey = passkey.to_string();
+		}
+		Ok(())
+	}
+
+	async fn insert_session(
+		&self,
+		session_id: &str,
+		account_id: &str,
+		expires_at: DateTime<Utc>,
+	) -> Result<(), FriendVoteError> {
+		let mut accounts = self.accounts.lock().expect("memory store lock poisoned");
+		let now = Utc::now();
+		accounts.sessions.retain(|_, (_, expires_at)| now < *expires_at);
+		accounts
+			.sessions
+			.insert(session_id.to_string(), (account_id.to_string(), expires_at));
+		Ok(())
+	}
+
+	async fn get_session_account(&self, session_id: &str) -> Result<Option<String>, FriendVoteError> {
+		let accounts = self.accounts.lock().expect("memory store lock poisoned");
+		Ok(accounts
+			.sessions
+			.get(session_id)
+			.filter(|(_, expires_at)| Utc::now() < *expires_at)
+			.map(|(account_id, _)| account_id.clone()))
+	}
+
+	async fn delete_session(&self, session_id: &str) -> Result<(), FriendVoteError> {
+		let mut accounts = self.accounts.lock().expect("memory store lock poisoned");
+		accounts.sessions.remove(session_id);
+		Ok(())
+	}
+}
@@ -0,0 +1,363 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+mod db;
+mod memory;
+mod results_cache;
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+
+use crate::ballot::{Ballot, WeightedBallot};
+use crate::endpoints::ElectionResults;
+use crate::errors::FriendVoteError;
+
+pub use db::DbStore;
+pub use memory::MemoryStore;
+pub use results_cache::ResultsCacheMode;
+
+/// The store used by the server functions and the API, provided through the leptos context and axum state.
+pub type Store = Arc<dyn ElectionStore>;
+
+pub struct NewElectionRecord {
+	pub election_id: String,
+	pub name: String,
+	pub candidates: Vec<String>,
+	pub shuffle_candidates: bool,
+	pub review_ballots: bool,
+	pub tokens: ElectionTokens,
+	pub owner_account_id: Option<String>,
+	pub members_only: bool,
+}
+
+#[derive(Clone)]
+pub struct ElectionInfo {
+	pub election_id: String,
+	pub name: String,
+	pub candidates: Vec<String>,
+	pub voting_open: bool,
+	/// Changes whenever something that the results depend on changes, such as a new ballot.
+	pub results_version: u64,
+	/// Short code to find the election by, only while voting is open.
+	pub join_code: Option<String>,
+	/// Whether each voter gets the candidates in a random order, rather than in the order they were entered.
+	pub shuffle_candidates: bool,
+	/// Whether voters are asked to confirm their ranking, written out in words, before it is submitted.
+	pub review_ballots: bool,
+	/// The account that created the election, if it was created while logged in.
+	pub owner_account_id: Option<String>,
+	/// Whether only members of the OpenID Connect voter group can vote, once each.
+	pub members_only: bool,
+}
+
+/// The secret tokens that grant access to the admin and results pages of an election.
+#[derive(Clone)]
+pub struct ElectionTokens {
+	pub admin_id: String,
+	pub result_id: String,
+}
+
+/// An optional organizer account. Accounts without a password can only log in with a passkey,
+/// or through OpenID Connect.
+#[derive(Clone)]
+pub struct Account {
+	pub account_id: String,
+	pub username: String,
+	/// Argon2 hash in the PHC string format.
+	pub password_hash: Option<String>,
+	/// The subject identifier at the OpenID Connect provider, for accounts that log in there.
+	pub oidc_subject: Option<String>,
+	/// Whether the subject was in the voter group at its latest OpenID Connect login.
+	pub oidc_member: bool,
+}
+
+/// An election as listed for the account that owns it.
+#[derive(Clone)]
+pub struct OwnedElection {
+	pub election_id: String,
+	pub name: String,
+}
+
+#[derive(Clone)]
+pub struct AuditEntry {
+	pub created_at: DateTime<Utc>,
+	pub action: String,
+}
+
+/// How many ballots an election has, and when the latest one was cast. Published after every ballot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoteSummary {
+	pub election_id: String,
+	pub n_votes: u64,
+	pub last_vote_at: Option<DateTime<Utc>>,
+}
+
+/// Number of vote summaries a slow subscriber can fall behind before it misses some.
+/// Only the latest summary matters, so missing some is harmless.
+const VOTE_EVENTS_CAPACITY: usize = 256;
+
+fn vote_events_channel() -> broadcast::Sender<VoteSummary> {
+	broadcast::channel(VOTE_EVENTS_CAPACITY).0
+}
+
+/// The number of ballots of an election, and for each ordered pair of candidates
+/// how many ballots rank the first above the second. The diagonal is `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PairwiseCounts {
+	pub n_votes: u64,
+	pub defeats_matrix: Vec<Vec<Option<u64>>>,
+}
+
+impl PairwiseCounts {
+	pub fn empty(n_candidates: usize) -> Self {
+		let defeats_matrix = (0..n_candidates)
+			.map(|winner| {
+				(0..n_candidates)
+					.map(|loser| {
+						if winner == loser {
+							None
+						} else {
+							Some(0)
+						}
+					})
+					.collect()
+			})
+			.collect();
+		Self {
+			n_votes: 0,
+			defeats_matrix,
+		}
+	}
+
+	/// Tallies the ballots from scratch.
+	pub fn from_ballots(n_candidates: usize, weighted_ballots: &[WeightedBallot]) -> Self {
+		let mut counts = Self::empty(n_candidates);
+		for weighted in weighted_ballots {
+			counts.add_ballot(&weighted.ballot, weighted.weight);
+		}
+		counts
+	}
+
+	pub fn add_ballot(&mut self, ballot: &Ballot, weight: u64) {
+		ballot.add_to_defeats_matrix(&mut self.defeats_matrix, weight);
+		self.n_votes += weight;
+	}
+}
+
+#[async_trait::async_trait]
+pub trait ElectionStore: Send + Sync {
+	async fn insert_election(&self, election: NewElectionRecord) -> Result<(), FriendVoteError>;
+
+	/// Fails with [`FriendVoteError::NotFound`] if the election does not exist.
+	async fn get_election(&self, election_id: &str) -> Result<ElectionInfo, FriendVoteError>;
+
+	/// Closing voting also frees the join code of the election.
+	async fn set_voting_open(&self, election_id: &str, voting_open: bool) -> Result<(), FriendVoteError>;
+
+	/// Gives the election the join code, unless another election already has it. Returns whether it did.
+	async fn set_join_code(&self, election_id: &str, join_code: &str) -> Result<bool, FriendVoteError>;
+
+	/// Returns the id of the election with the join code, if any.
+	async fn find_election_by_join_code(&self, join_code: &str) -> Result<Option<String>, FriendVoteError>;
+
+	async fn get_tokens(&self, election_id: &str) -> Result<ElectionTokens, FriendVoteError>;
+
+	/// Stores the ballot and adds it to the pairwise counts and the ballot patterns, atomically.
+	/// Then publishes the new [`VoteSummary`] to the subscribers of [`ElectionStore::subscribe_votes`].
+	/// With a `voter`, also records that the account voted, in the same transaction, and fails with
+	/// [`FriendVoteError::CannotVote`] if it already did. The record does not say which ballot is theirs.
+	async fn insert_ballot(
+		&self,
+		election_id: &str,
+		ballot: &Ballot,
+		voter: Option<&str>,
+	) -> Result<(), FriendVoteError>;
+
+	/// Returns whether the account voted in the election, as recorded by [`ElectionStore::insert_ballot`].
+	async fn has_voted(&self, election_id: &str, account_id: &str) -> Result<bool, FriendVoteError>;
+
+	async fn get_vote_summary(&self, election_id: &str) -> Result<VoteSummary, FriendVoteError>;
+
+	/// Receives the vote summaries of all elections, from the moment of subscribing.
+	fn subscribe_votes(&self) -> broadcast::Receiver<VoteSummary>;
+
+	/// Returns the distinct ballots with their number of voters, sorted as by [`WeightedBallot::aggregate`].
+	async fn get_ballot_patterns(&self, election_id: &str) -> Result<Vec<WeightedBallot>, FriendVoteError>;
+
+	/// Returns the incrementally maintained tally, without reading the individual ballots.
+	async fn get_pairwise_counts(&self, election_id: &str) -> Result<PairwiseCounts, FriendVoteError>;
+
+	/// Recomputes the pairwise counts and ballot patterns from the stored ballots,
+	/// and overwrites them if they disagree. Memory use grows with the number of distinct ballots,
+	/// not with the number of voters. Returns whether the stored tallies were wrong.
+	async fn rebuild_tallies(&self, election_id: &str) -> Result<bool, FriendVoteError>;
+
+	async fn get_election_ids(&self) -> Result<Vec<String>, FriendVoteError>;
+
+	/// Returns the cached results of the tally `configuration`, if they were computed at `results_version`.
+	async fn get_cached_results(
+		&self,
+		election_id: &str,
+		configuration: &str,
+		results_version: u64,
+	) -> Result<Option<ElectionResults>, FriendVoteError>;
+
+	async fn cache_results(
+		&self,
+		election_id: &str,
+		configuration: &str,
+		results_version: u64,
+		results: &ElectionResults,
+	) -> Result<(), FriendVoteError>;
+
+	async fn insert_audit_entry(&self, election_id: &str, action: &str) -> Result<(), FriendVoteError>;
+
+	/// Returns the audit entries of an election, oldest first.
+	async fn get_audit_entries(&self, election_id: &str) -> Result<Vec<AuditEntry>, FriendVoteError>;
+
+	/// Stores the account, unless another account already has its username or OpenID Connect subject.
+	/// Returns whether it did.
+	async fn insert_account(&self, account: Account) -> Result<bool, FriendVoteError>;
+
+	/// Fails with [`FriendVoteError::NotFound`] if the account does not exist.
+	async fn get_account(&self, account_id: &str) -> Result<Account, FriendVoteError>;
+
+	async fn find_account_by_username(&self, username: &str) -> Result<Option<Account>, FriendVoteError>;
+
+	async fn find_account_by_oidc_subject(&self, subject: &str) -> Result<Option<Account>, FriendVoteError>;
+
+	/// Records whether the account's OpenID Connect subject is in the voter group.
+	async fn set_oidc_member(&self, account_id: &str, member: bool) -> Result<(), FriendVoteError>;
+
+	/// Returns the elections created by the account, sorted by name.
+	async fn get_owned_elections(&self, account_id: &str) -> Result<Vec<OwnedElection>, FriendVoteError>;
+
+	/// Stores a passkey of the account, serialized by the caller, under its credential id.
+	async fn insert_passkey(
+		&self,
+		account_id: &str,
+		credential_id: &str,
+		passkey: &str,
+	) -> Result<(), FriendVoteError>;
+
+	/// Returns the serialized passkeys of the account.
+	async fn get_passkeys(&self, account_id: &str) -> Result<Vec<String>, FriendVoteError>;
+
+	/// Overwrites a stored passkey, such as after its signature counter changed.
+	async fn update_passkey(&self, credential_id: &str, passkey: &str) -> Result<(), FriendVoteError>;
+
+	async fn insert_session(
+		&self,
+		session_id: &str,
+		account_id: &str,
+		expires_at: DateTime<Utc>,
+	) -> Result<(), FriendVoteError>;
+
+	/// Returns the account logged in with the session, if the session exists and has not expired.
+	async fn get_session_account(&self, session_id: &str) -> Result<Option<String>, FriendVoteError>;
+
+	async fn delete_session(&self, session_id: &str) -> Result<(), FriendVoteError>;
+}
+
+fn election_not_found(election_id: &str) -> FriendVoteError {
+	FriendVoteError::NotFound(format!("Could not find election with id {}", election_id))
+}
+
+fn already_voted() -> FriendVoteError {
+	FriendVoteError::CannotVote("You already voted in this election.".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Votes as the ballot page sends them: the candidates in order, and for each pair of consecutive
+	/// candidates whether the first is preferred. Generated by xorshift64, so the tests are reproducible.
+	pub(super) fn random_votes(n_candidates: usize, n_votes: usize) -> Vec<(Vec<usize>, Vec<bool>)> {
+		let mut state = 0x2545_F491_4F6C_DD1Du64;
+		let mut next = move || {
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			state
+		};
+		(0..n_votes)
+			.map(|_| {
+				// rotations of one order, so that many ballots are identical
+				let rotation = next() as usize % n_candidates;
+				let cand_order = (0..n_candidates)
+					.map(|ind| (ind + rotation) % n_candidates)
+					.collect();
+				let comp_is_bigger = (1..n_candidates).map(|_| next() % 4 != 0).collect();
+				(cand_order, comp_is_bigger)
+			})
+			.collect()
+	}
+
+	/// The tally as it was computed before ballots were stored as groups: every vote on its own.
+	fn per_vote_defeats_matrix(
+		n_candidates: usize,
+		votes: &[(Vec<usize>, Vec<bool>)],
+	) -> Vec<Vec<Option<u64>>> {
+		let mut defeats_matrix = Vec::with_capacity(n_candidates);
+		for i in 0..n_candidates {
+			defeats_matrix.push(vec![Some(0u64); n_candidates]);
+			defeats_matrix[i][i] = None;
+		}
+		for (cand_order, comp_is_bigger) in votes {
+			for (i, ind) in cand_order.iter().enumerate() {
+				let mut bigger = false;
+				for j in (i + 1)..cand_order.len() {
+					bigger = bigger || comp_is_bigger[j - 1];
+					if bigger {
+						defeats_matrix[*ind][cand_order[j]] =
+							defeats_matrix[*ind][cand_order[j]].map(|x| x + 1);
+					}
+				}
+			}
+		}
+		defeats_matrix
+	}
+
+	#[test]
+	fn aggregated_tally_matches_per_vote_tally() {
+		let n_candidates = 6;
+		let votes = random_votes(n_candidates, 500);
+		let ballots = votes
+			.iter()
+			.map(|(cand_order, comp_is_bigger)| Ballot::from_order(cand_order, comp_is_bigger))
+			.collect::<Vec<_>>();
+
+		let mut incremental = PairwiseCounts::empty(n_candidates);
+		for ballot in ballots.iter() {
+			incremental.add_ballot(ballot, 1);
+		}
+		let patterns = WeightedBallot::aggregate(&ballots);
+		assert!(patterns.len() < ballots.len());
+		let aggregated = PairwiseCounts::from_ballots(n_candidates, &patterns);
+
+		assert_eq!(incremental, aggregated);
+		assert_eq!(aggregated.n_votes, votes.len() as u64);
+		assert_eq!(aggregated.defeats_matrix, per_vote_defeats_matrix(n_candidates, &votes));
+	}
+
+	#[test]
+	fn ties_count_for_neither_candidate() {
+		let mut counts = PairwiseCounts::empty(3);
+		counts.add_ballot(&Ballot::from_order(&[1, 0, 2], &[false, true]), 2);
+		assert_eq!(counts.n_votes, 2);
+		assert_eq!(
+			counts.defeats_matrix,
+			vec![vec![None, Some(0), Some(2)], vec![Some(0), None, Some(2)], vec![Some(0), Some(0), None],]
+		);
+	}
+}
@@ -0,0 +1,61 @@
+/// Copyright 2025 Simon De Ridder
+/// This file is part of FriendVote.
+/// FriendVote is free software: you can redistribute it and/or modify it under the terms of the
+/// GNU General Public License as published by the Free Software Foundation, either version 3 of the License,
+/// or (at your option) any later version.
+/// FriendVote is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY;
+/// without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+/// See the GNU General Public License for more details.
+/// You should have received a copy of the GNU General Public License along with FriendVote.
+/// If not, see <https://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::endpoints::ElectionResults;
+
+/// Where a [`super::DbStore`] keeps computed results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResultsCacheMode {
+	/// In the memory of the server process, for a single server instance.
+	Memory,
+	/// In the `results_cache` table, shared by all server instances using the database.
+	Database,
+}
+
+/// Results cached in process memory, one entry per election and tally configuration.
+#[derive(Default)]
+pub(super) struct MemoryResultsCache {
+	entries: Mutex<HashMap<(String, String), (u64, ElectionResults)>>,
+}
+
+impl MemoryResultsCache {
+	pub fn get(
+		&self,
+		election_id: &str,
+		configuration: &str,
+		results_version: u64,
+	) -> Option<ElectionResults> {
+		let entries = self.entries.lock().expect("results cache lock poisoned");
+		entries
+			.get(&(election_id.to_string(), configuration.to_string()))
+			.filter(|(version, _)| *version == results_version)
+			.map(|(_, results)| results.clone())
+	}
+
+	/// Stores the results, unless results of a newer version are already cached.
+	pub fn put(
+		&self,
+		election_id: &str,
+		configuration: &str,
+		results_version: u64,
+		results: &ElectionResults,
+	) {
+		let mut entries = self.entries.lock().expect("results cache lock poisoned");
+		let entry = entries
+			.entry((election_id.to_string(), configuration.to_string()))
+			.or_insert_with(|| (results_version, results.clone()));
+		if entry.0 <= results_version {
+			*entry = (results_version, results.clone());
+		}
+	}
+}
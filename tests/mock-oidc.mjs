@@ -0,0 +1,125 @@
+// A mock OpenID Connect provider, for trying out and testing the OIDC login without a real identity provider.
+// It has no dependencies: run it with `node mock-oidc.mjs`, and start FriendVote with
+//   OIDC_ISSUER_URL=http://localhost:3100 OIDC_CLIENT_ID=friendvote OIDC_CLIENT_SECRET=secret OIDC_VOTER_GROUP=voters
+// Its login page asks for a username and groups instead of a password, and logs in whoever is typed.
+import { createServer } from "node:http";
+import { createHash, generateKeyPairSync, randomBytes, sign } from "node:crypto";
+
+const port = Number(process.env.MOCK_OIDC_PORT ?? 3100);
+const issuer = `http://localhost:${port}`;
+const { publicKey, privateKey } = generateKeyPairSync("rsa", { modulusLength: 2048 });
+const jwk = { ...publicKey.export({ format: "jwk" }), kid: "mock", use: "sig", alg: "RS256" };
+/** The logins waiting for their authorization code to be redeemed, by code. */
+const codes = new Map();
+
+const base64url = (data) => Buffer.from(data).toString("base64url");
+const escapeHtml = (text) =>
+  String(text).replace(/[&<>"']/g, (character) => `&#${character.charCodeAt(0)};`);
+
+function idToken(claims) {
+  const header = base64url(JSON.stringify({ alg: "RS256", typ: "JWT", kid: jwk.kid }));
+  const payload = base64url(JSON.stringify(claims));
+  const signature = sign("sha256", Buffer.from(`${header}.${payload}`), privateKey);
+  return `${header}.${payload}.${base64url(signature)}`;
+}
+
+function json(response, status, body) {
+  response.writeHead(status, { "Content-Type": "application/json", "Cache-Control": "no-store" });
+  response.end(JSON.stringify(body));
+}
+
+function loginPage(response, query) {
+  const hidden = ["client_id", "redirect_uri", "state", "nonce", "code_challenge"]
+    .map((name) => `<input type="hidden" name="${name}" value="${escapeHtml(query.get(name) ?? "")}">`)
+    .join("");
+  response.writeHead(200, { "Content-Type": "text/html" });
+  response.end(`<!DOCTYPE html>
+<title>Mock identity provider</title>
+<h1>Mock identity provider</h1>
+<form method="get" action="/authorize/approve">
+  ${hidden}
+  <p><label for="mock_username">Username: </label><input id="mock_username" name="username" required></p>
+  <p><label for="mock_groups">Groups, separated by commas: </label><input id="mock_groups" name="groups"></p>
+  <button type="submit">Sign in</button>
+</form>`);
+}
+
+function approve(response, query) {
+  const code = randomBytes(16).toString("hex");
+  codes.set(code, {
+    username: query.get("username"),
+    groups: (query.get("groups") ?? "")
+      .split(",")
+      .map((group) => group.trim())
+      .filter((group) => group !== ""),
+    clientId: query.get("client_id"),
+    redirectUri: query.get("redirect_uri"),
+    nonce: query.get("nonce"),
+    codeChallenge: query.get("code_challenge"),
+  });
+  const redirect = new URL(query.get("redirect_uri"));
+  redirect.searchParams.set("code", code);
+  redirect.searchParams.set("state", query.get("state"));
+  response.writeHead(302, { Location: redirect.toString() });
+  response.end();
+}
+
+function token(response, form) {
+  const login = codes.get(form.get("code"));
+  codes.delete(form.get("code"));
+  const verifier = form.get("code_verifier") ?? "";
+  if (
+    !login ||
+    login.redirectUri !== form.get("redirect_uri") ||
+    (login.codeChallenge && base64url(createHash("sha256").update(verifier).digest()) !== login.codeChallenge)
+  ) {
+    json(response, 400, { error: "invalid_grant" });
+    return;
+  }
+  const now = Math.floor(Date.now() / 1000);
+  json(response, 200, {
+    access_token: randomBytes(16).toString("hex"),
+    token_type: "Bearer",
+    expires_in: 300,
+    id_token: idToken({
+      iss: issuer,
+      sub: `mock-${login.username}`,
+      aud: login.clientId,
+      iat: now,
+      exp: now + 300,
+      nonce: login.nonce,
+      preferred_username: login.username,
+      groups: login.groups,
+    }),
+  });
+}
+
+createServer((request, response) => {
+  const url = new URL(request.url, issuer);
+  if (url.pathname === "/.well-known/openid-configuration") {
+    json(response, 200, {
+      issuer,
+      authorization_endpoint: `${issuer}/authorize`,
+      token_endpoint: `${issuer}/token`,
+      jwks_uri: `${issuer}/jwks`,
+      response_types_supported: ["code"],
+      subject_types_supported: ["public"],
+      id_token_signing_alg_values_supported: ["RS256"],
+      token_endpoint_auth_methods_supported: ["client_secret_basic", "client_secret_post"],
+      code_challenge_methods_supported: ["S256"],
+    });
+  } else if (url.pathname === "/jwks") {
+    json(response, 200, { keys: [jwk] });
+  } else if (url.pathname === "/authorize") {
+    loginPage(response, url.searchParams);
+  } else if (url.pathname === "/authorize/approve") {
+    approve(response, url.searchParams);
+  } else if (url.pathname === "/token" && request.method === "POST") {
+    let body = "";
+    request.on("data", (chunk) => (body += chunk));
+    request.on("end", () => token(response, new URLSearchParams(body)));
+  } else {
+    response.writeHead(404);
+    response.end();
+  }
+}).listen(port, () => console.log(`mock OpenID Connect provider at ${issuer}`));
@@ -0,0 +1,77 @@
+import { test, expect, type Page } from "@playwright/test";
+
+/** Usernames are unique on the server, and the tests run in parallel. */
+function uniqueUsername(): string {
+  return `organizer-${Date.now()}-${Math.random().toString(36).slice(2, 8)}`;
+}
+
+async function signUp(page: Page, username: string) {
+  await page.goto("http://localhost:3000/account");
+  await page.locator("#sign_up_username").fill(username);
+  await page.locator("#sign_up_password").fill("correct horse battery");
+  await page.getByRole("button", { name: "Sign up" }).click();
+  await expect(page.getByText(`Logged in as ${username}`)).toBeVisible();
+}
+
+test("elections created while logged in are managed from the account", async ({ page }) => {
+  const username = uniqueUsername();
+  await signUp(page, username);
+
+  await page.goto("http://localhost:3000/");
+  await page.locator("#election_name").fill("Team lunch");
+  await page.locator("#candidate_0").fill("Pizza");
+  await page.locator("#candidate_1").fill("Sushi");
+  await page.getByRole("button", { name: "Submit" }).click();
+  await expect(page.locator("h1")).toHaveText("Admin page for 'Team lunch'");
+
+  await page.goto("http://localhost:3000/account");
+  await page.getByRole("link", { name: "Team lunch" }).click();
+  await expect(page).toHaveURL(/\/admin$/);
+  await expect(page.locator("h1")).toHaveText("Admin page for 'Team lunch'");
+  await expect(page.getByText("No votes yet.")).toBeVisible();
+
+  await page.goto("http://localhost:3000/account");
+  await page.getByRole("button", { name: "Log out" }).click();
+  await expect(page.getByRole("heading", { name: "Sign up" })).toBeVisible();
+  await page.locator("#log_in_username").fill(username);
+  await page.locator("#log_in_password").fill("not the password");
+  await page.getByRole("button", { name: "Log in", exact: true }).click();
+  await expect(page.getByText("Wrong username or password.")).toBeVisible();
+});
+
+test("owners need to be logged in to skip the admin link", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Book club", candidates: ["Dune", "Emma"] },
+  });
+  const election = await created.json();
+
+  await page.goto(`http://localhost:3000/${election.election_id}/admin`);
+  await expect(page.getByText("Log in to manage this election without its admin link.")).toBeVisible();
+});
+
+test("a passkey logs in without the password", async ({ page, browserName }) => {
+  test.skip(browserName !== "chromium", "virtual authenticators need the Chrome DevTools Protocol");
+  // a software authenticator, which confirms every request as if the user touched it
+  const client = await page.context().newCDPSession(page);
+  await client.send("WebAuthn.enable");
+  await client.send("WebAuthn.addVirtualAuthenticator", {
+    options: {
+      protocol: "ctap2",
+      transport: "internal",
+      hasResidentKey: true,
+      hasUserVerification: true,
+      isUserVerified: true,
+    },
+  });
+
+  const username = uniqueUsername();
+  await signUp(page, username);
+  await expect(page.getByText("You have no passkeys yet.")).toBeVisible();
+  await page.getByRole("button", { name: "Add a passkey" }).click();
+  await expect(page.getByText("You have 1 passkey.")).toBeVisible();
+
+  await page.getByRole("button", { name: "Log out" }).click();
+  await page.locator("#passkey_username").fill(username);
+  await page.getByRole("button", { name: "Log in with a passkey" }).click();
+  await expect(page.getByText(`Logged in as ${username}`)).toBeVisible();
+});
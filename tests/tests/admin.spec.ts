@@ -0,0 +1,30 @@
+import { test, expect } from "@playwright/test";
+
+test("admin page counts votes live", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Live count", candidates: ["Apples", "Pears"] },
+  });
+  const election = await created.json();
+
+  await page.goto(`http://localhost:3000/${election.admin_path}`);
+  await expect(page.getByText("No votes yet.")).toBeVisible();
+
+  await request.post(`http://localhost:3000/api/v1/elections/${election.election_id}/votes`, {
+    data: { ranking: [["Pears"], ["Apples"]] },
+  });
+  await expect(page.getByText(/^1 vote, the latest at/)).toBeVisible();
+});
+
+test("admin page shows QR codes and a join card", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Team event", candidates: ["Bowling", "Karaoke"] },
+  });
+  const election = await created.json();
+
+  await page.goto(`http://localhost:3000/${election.admin_path}`);
+  const joinCard = page.locator(".join-card");
+  await expect(joinCard.getByText("Team event")).toBeVisible();
+  await expect(joinCard.locator(".qr-code svg")).toBeVisible();
+  await expect(joinCard.getByText(`http://localhost:3000/${election.vote_path}`)).toBeVisible();
+  await expect(page.locator(".election-link .qr-code svg")).toHaveCount(3);
+});
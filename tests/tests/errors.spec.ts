@@ -0,0 +1,14 @@
+import { test, expect } from "@playwright/test";
+
+test("unknown page shows a not found page", async ({ page }) => {
+  const response = await page.goto("http://localhost:3000/this/page/does/not/exist");
+
+  expect(response?.status()).toBe(404);
+  await expect(page.locator("h1")).toHaveText("Page not found");
+});
+
+test("unknown election shows an explanation", async ({ page }) => {
+  await page.goto("http://localhost:3000/unknownelection/vote");
+
+  await expect(page.locator("h1")).toHaveText("Election not found");
+});
@@ -7,3 +7,58 @@ test("homepage has title and heading text", async ({ page }) => {
 
   await expect(page.locator("h1")).toHaveText("Welcome to FriendVote!");
 });
+
+test("join code leads to the vote page", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Quiz night", candidates: ["Pizza", "Sushi"] },
+  });
+  const election = await created.json();
+  const joinCode = await request.post(
+    `http://localhost:3000/api/v1/elections/${election.election_id}/admin/${election.admin_id}/join_code`,
+  );
+  const { join_code } = await joinCode.json();
+  expect(join_code).toMatch(/^[2-9A-HJKMNP-Z]{6}$/);
+
+  await page.goto("http://localhost:3000/");
+  await page.getByLabel("Have a join code?").fill(join_code.toLowerCase());
+  await page.getByRole("button", { name: "Join" }).click();
+  await expect(page).toHaveURL(`http://localhost:3000/${election.vote_path}`);
+});
+
+test("created elections are listed, and move to another browser through an encrypted file", async ({
+  page,
+  browser,
+  request,
+}) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Board games", candidates: ["Catan", "Azul"] },
+  });
+  const election = await created.json();
+  await page.goto(`http://localhost:3000/${election.admin_path}`);
+  await expect(page.locator("h1")).toHaveText("Admin page for 'Board games'");
+
+  await page.goto("http://localhost:3000/");
+  const link = page.locator(".my-elections").getByRole("link", { name: "Board games" });
+  await expect(link).toHaveAttribute("href", `/${election.admin_path}`);
+
+  await page.getByText("Move my elections to another device").click();
+  await page.getByLabel("Passphrase").fill("correct horse");
+  const downloading = page.waitForEvent("download");
+  await page.getByRole("button", { name: "Export" }).click();
+  const exportFile = await (await downloading).path();
+
+  // a fresh browser context has an empty local storage, like another device
+  const other = await (await browser.newContext()).newPage();
+  await other.goto("http://localhost:3000/");
+  await other.getByText("Move my elections to another device").click();
+  await other.getByLabel("Passphrase").fill("wrong");
+  await other.getByLabel("Import a file").setInputFiles(exportFile);
+  await expect(other.getByText("Wrong passphrase, or the file was changed.")).toBeVisible();
+  await other.getByLabel("Passphrase").fill("correct horse");
+  await other.getByLabel("Import a file").setInputFiles(exportFile);
+  await expect(other.getByText("Imported 1 new election.")).toBeVisible();
+  await expect(other.locator(".my-elections").getByRole("link", { name: "Board games" })).toHaveAttribute(
+    "href",
+    `/${election.admin_path}`,
+  );
+});
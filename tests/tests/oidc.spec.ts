@@ -0,0 +1,55 @@
+import { test, expect, type Page } from "@playwright/test";
+
+// needs the server to log in through the mock provider in mock-oidc.mjs, see the README
+test.skip(!process.env.MOCK_OIDC, "set MOCK_OIDC=1 when the server uses the mock OpenID Connect provider");
+
+/** Subjects are linked to accounts on the server, and the tests run in parallel. */
+function uniqueUsername(name: string): string {
+  return `${name}-${Date.now()}-${Math.random().toString(36).slice(2, 8)}`;
+}
+
+/** Signs in on the login page of the mock provider, which logs in whoever is typed. */
+async function signInAtProvider(page: Page, username: string, groups: string) {
+  await page.locator("#mock_username").fill(username);
+  await page.locator("#mock_groups").fill(groups);
+  await page.getByRole("button", { name: "Sign in" }).click();
+}
+
+test("only members of the voter group vote in members-only elections, once each", async ({ browser }) => {
+  const organizer = await browser.newPage();
+  const organizerName = uniqueUsername("organizer");
+  await organizer.goto("http://localhost:3000/account");
+  await organizer.getByRole("link", { name: "Log in with OpenID Connect" }).click();
+  await signInAtProvider(organizer, organizerName, "");
+  await expect(organizer.getByText(`Logged in as ${organizerName}`)).toBeVisible();
+
+  await organizer.goto("http://localhost:3000/");
+  await organizer.locator("#election_name").fill("Board election");
+  await organizer.locator("#candidate_0").fill("Ada");
+  await organizer.locator("#candidate_1").fill("Grace");
+  await organizer.locator("#members_only").check();
+  await organizer.getByRole("button", { name: "Submit" }).click();
+  await expect(organizer.locator("h1")).toHaveText("Admin page for 'Board election'");
+  const votePath = new URL(organizer.url()).pathname.replace(/\/admin\/.*$/, "/vote");
+
+  // each voter gets their own browser context, so their own session
+  const outsider = await (await browser.newContext()).newPage();
+  await outsider.goto(`http://localhost:3000${votePath}`);
+  await expect(outsider.getByText("Only members can vote in this election.")).toBeVisible();
+  await outsider.getByRole("link", { name: "Log in to vote" }).click();
+  await signInAtProvider(outsider, uniqueUsername("outsider"), "staff");
+  await expect(outsider.locator("h1")).toHaveText("Cannot vote");
+  await expect(outsider.getByText("Only members of the voter group can vote in this election.")).toBeVisible();
+
+  const member = await (await browser.newContext()).newPage();
+  await member.goto(`http://localhost:3000${votePath}`);
+  await member.getByRole("link", { name: "Log in to vote" }).click();
+  await signInAtProvider(member, uniqueUsername("member"), "staff, voters");
+  await expect(member.locator("h1")).toHaveText("Voting page for 'Board election'");
+  await member.getByRole("button", { name: "Submit" }).click();
+  await expect(member.getByText("Thank you for voting!")).toBeVisible();
+
+  await member.goto(`http://localhost:3000${votePath}`);
+  await expect(member.locator("h1")).toHaveText("Cannot vote");
+  await expect(member.getByText("You already voted in this election.")).toBeVisible();
+});
@@ -0,0 +1,44 @@
+import { test, expect } from "@playwright/test";
+
+test("results page updates as votes come in", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Live results", candidates: ["Apples", "Pears"] },
+  });
+  const election = await created.json();
+  const vote = (ranking: string[][]) =>
+    request.post(`http://localhost:3000/api/v1/elections/${election.election_id}/votes`, {
+      data: { ranking },
+    });
+  await vote([["Apples"], ["Pears"]]);
+
+  await page.goto(`http://localhost:3000/${election.result_path}`);
+  await expect(page.getByText("Number of votes: 1")).toBeVisible();
+  await expect(page.getByText("Winner: Apples")).toBeVisible();
+
+  await vote([["Pears"], ["Apples"]]);
+  await vote([["Pears"], ["Apples"]]);
+  await expect(page.getByText("Number of votes: 3")).toBeVisible();
+  await expect(page.getByText("Winner: Pears")).toBeVisible();
+});
+
+test("presentation reveals the ranking from last to first", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Game night", candidates: ["Chess", "Go", "Risk"] },
+  });
+  const election = await created.json();
+  await request.post(`http://localhost:3000/api/v1/elections/${election.election_id}/votes`, {
+    data: { ranking: [["Go"], ["Chess"], ["Risk"]] },
+  });
+
+  await page.goto(`http://localhost:3000/${election.election_id}/present/${election.result_id}`);
+  await expect(page.locator(".qr-code svg")).toBeVisible();
+  const ranks = page.locator(".presentation-ranks > li .candidates");
+  await expect(ranks).toHaveText(["?", "?", "?"]);
+
+  await page.getByRole("button", { name: "Reveal next" }).click();
+  await expect(ranks).toHaveText(["?", "?", "Risk"]);
+  await page.keyboard.press("ArrowRight");
+  await page.keyboard.press("ArrowRight");
+  await expect(ranks).toHaveText(["Go", "Chess", "Risk"]);
+  await expect(page.getByText("Go beats Chess (1–0), Risk (1–0)")).toBeVisible();
+});
@@ -0,0 +1,241 @@
+import { test, expect } from "@playwright/test";
+
+test("ranking is reordered by dragging and ties are toggled", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Lunch", candidates: ["Apples", "Pears", "Plums"] },
+  });
+  const election = await created.json();
+
+  await page.goto(`http://localhost:3000/${election.vote_path}`);
+  const labels = page.locator(".drag-list-label");
+  await expect(labels).toHaveText(["Apples", "Pears", "Plums"]);
+
+  // drag Plums to the top
+  const plums = await labels.nth(2).boundingBox();
+  const apples = await labels.nth(0).boundingBox();
+  await page.mouse.move(plums!.x + plums!.width / 2, plums!.y + plums!.height / 2);
+  await page.mouse.down();
+  await page.mouse.move(apples!.x + apples!.width / 2, apples!.y + apples!.height / 2, { steps: 10 });
+  await page.mouse.up();
+  await expect(labels).toHaveText(["Plums", "Apples", "Pears"]);
+
+  // tie Apples with Pears
+  await page.locator(".drag-list-item").nth(1).locator(".tie-toggle label").click();
+  await page.getByRole("button", { name: "Submit" }).click();
+  await expect(page.getByText("Thank you for voting!")).toBeVisible();
+
+  const results = await request.get(
+    `http://localhost:3000/api/v1/elections/${election.election_id}/results/${election.result_id}`,
+  );
+  expect((await results.json()).ballot_frequencies).toEqual([
+    { ranking: [["Plums"], ["Apples", "Pears"]], count: 1 },
+  ]);
+});
+
+test("ranking is reordered and tied with the keyboard", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Lunch", candidates: ["Apples", "Pears", "Plums"] },
+  });
+  const election = await created.json();
+
+  await page.goto(`http://localhost:3000/${election.vote_path}`);
+  const options = page.getByRole("option");
+  await expect(options).toHaveText(["Apples", "Pears", "Plums"]);
+
+  // move Plums up past Pears: first into a tie with it, then above it
+  await options.first().focus();
+  await page.keyboard.press("End");
+  await expect(page.getByRole("option", { name: "Plums" })).toBeFocused();
+  await page.keyboard.press("Alt+ArrowUp");
+  await expect(page.getByText("Plums, rank 2 of 2, tied with Pears")).toBeAttached();
+  await page.keyboard.press("Alt+ArrowUp");
+  await expect(options).toHaveText(["Apples", "Plums", "Pears"]);
+  await expect(page.getByText("Plums, rank 2 of 3")).toBeAttached();
+  await expect(page.getByRole("option", { name: "Plums" })).toBeFocused();
+
+  // tie Apples with Plums
+  await page.keyboard.press("ArrowUp");
+  await page.keyboard.press("t");
+  await expect(page.getByText("Apples tied with Plums")).toBeAttached();
+  await expect(page.getByRole("checkbox", { name: "Prefer Apples over Plums" })).not.toBeChecked();
+
+  await page.getByRole("button", { name: "Submit" }).click();
+  await expect(page.getByText("Thank you for voting!")).toBeVisible();
+
+  const results = await request.get(
+    `http://localhost:3000/api/v1/elections/${election.election_id}/results/${election.result_id}`,
+  );
+  expect((await results.json()).ballot_frequencies).toEqual([
+    { ranking: [["Apples", "Plums"], ["Pears"]], count: 1 },
+  ]);
+});
+
+test.describe("without JavaScript", () => {
+  test.use({ javaScriptEnabled: false });
+
+  test("ranks are chosen per candidate, equal ranks tying them", async ({ page, request }) => {
+    const created = await request.post("http://localhost:3000/api/v1/elections", {
+      data: { name: "Lunch", candidates: ["Apples", "Pears", "Plums"] },
+    });
+    const election = await created.json();
+
+    await page.goto(`http://localhost:3000/${election.vote_path}`);
+    await page.getByLabel("Apples").selectOption("3");
+    await page.getByLabel("Pears").selectOption("1");
+    await page.getByLabel("Plums").selectOption("1");
+    await page.getByRole("button", { name: "Submit" }).click();
+    await expect(page.getByText("Thank you for voting!")).toBeVisible();
+
+    const results = await request.get(
+      `http://localhost:3000/api/v1/elections/${election.election_id}/results/${election.result_id}`,
+    );
+    expect((await results.json()).ballot_frequencies).toEqual([
+      { ranking: [["Pears", "Plums"], ["Apples"]], count: 1 },
+    ]);
+  });
+
+  test("the ranking is reviewed in words on a page of its own", async ({ page, request }) => {
+    const created = await request.post("http://localhost:3000/api/v1/elections", {
+      data: { name: "Lunch", candidates: ["Apples", "Pears", "Plums"], review_ballots: true },
+    });
+    const election = await created.json();
+
+    await page.goto(`http://localhost:3000/${election.vote_path}`);
+    await expect(page.getByRole("button", { name: "Submit" })).toBeHidden();
+    await page.getByLabel("Apples").selectOption("3");
+    await page.getByLabel("Plums").selectOption("1");
+    await page.getByRole("button", { name: "Review my vote" }).click();
+    await expect(page.locator(".ballot-review-ranking")).toHaveText("Plums > Pears > Apples");
+    const before = await request.get(
+      `http://localhost:3000/api/v1/elections/${election.election_id}/results/${election.result_id}`,
+    );
+    expect((await before.json()).ballot_frequencies).toEqual([]);
+
+    await page.getByRole("button", { name: "Confirm my vote" }).click();
+    await expect(page.getByText("Thank you for voting!")).toBeVisible();
+    const results = await request.get(
+      `http://localhost:3000/api/v1/elections/${election.election_id}/results/${election.result_id}`,
+    );
+    expect((await results.json()).ballot_frequencies).toEqual([
+      { ranking: [["Plums"], ["Pears"], ["Apples"]], count: 1 },
+    ]);
+  });
+});
+
+test("candidates are shuffled for each voter when the election asks for it", async ({ page, request }) => {
+  const candidates = ["A", "B", "C", "D", "E", "F"];
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Shuffled", candidates, shuffle_candidates: true },
+  });
+  const election = await created.json();
+
+  const orders = new Set<string>();
+  for (let visit = 0; visit < 5; visit++) {
+    await page.goto(`http://localhost:3000/${election.vote_path}`);
+    const shown = await page.locator(".drag-list-label").allTextContents();
+    expect([...shown].sort()).toEqual(candidates);
+    orders.add(shown.join());
+  }
+  expect(orders.size).toBeGreaterThan(1);
+
+  // the submitted ranking is the shown order, whatever it was
+  const shown = await page.locator(".drag-list-label").allTextContents();
+  await page.getByRole("button", { name: "Submit" }).click();
+  await expect(page.getByText("Thank you for voting!")).toBeVisible();
+  const results = await request.get(
+    `http://localhost:3000/api/v1/elections/${election.election_id}/results/${election.result_id}`,
+  );
+  expect((await results.json()).ballot_frequencies).toEqual([{ ranking: shown.map((name) => [name]), count: 1 }]);
+});
+
+test("the ranking is reviewed in words before it is submitted", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Lunch", candidates: ["Apples", "Pears", "Plums"], review_ballots: true },
+  });
+  const election = await created.json();
+
+  await page.goto(`http://localhost:3000/${election.vote_path}`);
+  await expect(page.getByRole("button", { name: "Submit" })).toBeHidden();
+  await page.locator(".drag-list-item").nth(1).locator(".tie-toggle label").click();
+  await page.getByRole("button", { name: "Review my vote" }).click();
+  await expect(page.locator(".ballot-review-ranking")).toHaveText("Apples > Pears = Plums");
+
+  // going back keeps the ranking, and nothing is stored before confirming
+  await page.getByRole("button", { name: "Change my ranking" }).click();
+  await expect(page.locator(".drag-list-label")).toHaveText(["Apples", "Pears", "Plums"]);
+  const before = await request.get(
+    `http://localhost:3000/api/v1/elections/${election.election_id}/results/${election.result_id}`,
+  );
+  expect((await before.json()).ballot_frequencies).toEqual([]);
+
+  await page.getByRole("button", { name: "Review my vote" }).click();
+  await page.getByRole("button", { name: "Confirm my vote" }).click();
+  await expect(page.getByText("Thank you for voting!")).toBeVisible();
+  const results = await request.get(
+    `http://localhost:3000/api/v1/elections/${election.election_id}/results/${election.result_id}`,
+  );
+  expect((await results.json()).ballot_frequencies).toEqual([
+    { ranking: [["Apples"], ["Pears", "Plums"]], count: 1 },
+  ]);
+});
+
+test("the ranking is built by answering which of two candidates is preferred", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Lunch", candidates: ["Apples", "Pears", "Plums", "Figs"] },
+  });
+  const election = await created.json();
+  const ranks: Record<string, number> = { Plums: 1, Apples: 2, Pears: 2, Figs: 3 };
+  // a group of candidates answered to be equal is shown as "A = B"
+  const rankOf = (label: string) => ranks[label.split(" = ")[0]];
+
+  await page.goto(`http://localhost:3000/${election.vote_path}`);
+  await page.getByRole("button", { name: "Rank by answering questions instead" }).click();
+  const choices = page.locator(".pairwise-choices button");
+  for (let question = 1; ; question++) {
+    const asked = page.getByText(`Question ${question}: which do you prefer?`);
+    await expect(asked.or(page.locator(".drag-list"))).toBeVisible();
+    if (!(await asked.isVisible())) {
+      break;
+    }
+    const first = rankOf((await choices.nth(0).textContent())!);
+    const second = rankOf((await choices.nth(2).textContent())!);
+    await choices.nth(first < second ? 0 : first > second ? 2 : 1).click();
+  }
+
+  await expect(page.locator(".drag-list-label")).toHaveText(["Plums", "Apples", "Pears", "Figs"]);
+  await page.getByRole("button", { name: "Submit" }).click();
+  await expect(page.getByText("Thank you for voting!")).toBeVisible();
+  const results = await request.get(
+    `http://localhost:3000/api/v1/elections/${election.election_id}/results/${election.result_id}`,
+  );
+  expect((await results.json()).ballot_frequencies).toEqual([
+    { ranking: [["Plums"], ["Apples", "Pears"], ["Figs"]], count: 1 },
+  ]);
+});
+
+test("the ranking in progress survives a reload until it is submitted", async ({ page, request }) => {
+  const created = await request.post("http://localhost:3000/api/v1/elections", {
+    data: { name: "Lunch", candidates: ["Apples", "Pears", "Plums"] },
+  });
+  const election = await created.json();
+
+  await page.goto(`http://localhost:3000/${election.vote_path}`);
+  const options = page.getByRole("option");
+  await options.first().focus();
+  await page.keyboard.press("End");
+  await page.keyboard.press("Alt+ArrowUp");
+  await page.keyboard.press("Alt+ArrowUp");
+  await expect(options).toHaveText(["Apples", "Plums", "Pears"]);
+  await page.keyboard.press("ArrowUp");
+  await page.keyboard.press("t");
+
+  await page.reload();
+  await expect(options).toHaveText(["Apples", "Plums", "Pears"]);
+  await expect(page.getByRole("checkbox", { name: "Prefer Apples over Plums" })).not.toBeChecked();
+
+  await page.getByRole("button", { name: "Submit" }).click();
+  await expect(page.getByText("Thank you for voting!")).toBeVisible();
+  await page.goto(`http://localhost:3000/${election.vote_path}`);
+  await expect(page.getByRole("checkbox", { name: "Prefer Apples over Pears" })).toBeChecked();
+  await expect(options).toHaveText(["Apples", "Pears", "Plums"]);
+});